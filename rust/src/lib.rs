@@ -1,8 +1,23 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod merkle_core;
+
+#[cfg(feature = "std")]
 pub mod merkle_tree {
 
+    use crate::merkle_core;
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
     use crypto::digest::Digest;
     use crypto::sha2::Sha256;
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
     use std::result::Result;
+    use std::sync::{Arc, OnceLock};
     use std::vec::Vec;
 
     // Refactored common path to a helper function
@@ -22,493 +37,8084 @@ pub mod merkle_tree {
         hasher(format!("{left}{right}").as_str())
     }
 
-    #[allow(dead_code)]
-    #[derive(Clone, Debug, Default)]
-    pub struct MerkleNode {
-        value: String,
-        left: Option<Box<MerkleNode>>,
-        right: Option<Box<MerkleNode>>,
+    // hash of the empty string, the leaf padding an odd number of elements in the default
+    // scheme, computed once so verifiers can recognise padding siblings without rehashing
+    //
+    // the empty node given to the last node of an odd row above the leaves is not this hash,
+    // it contributes nothing to the hashed input
+    pub fn empty_node_hash() -> &'static str {
+        static EMPTY_NODE_HASH: OnceLock<String> = OnceLock::new();
+
+        EMPTY_NODE_HASH.get_or_init(|| hash_leaf(""))
     }
 
-    impl From<String> for MerkleNode {
-        fn from(value: String) -> Self {
-            MerkleNode {
-                value: hash_leaf(&value),
-                left: None,
-                right: None,
-            }
+    // raise a root by one level, pairing it with a sibling from some larger structure so that
+    // subtrees can be stitched together without rebuilding either of them
+    pub fn extend_root(root: &str, sibling: &str, sibling_on_left: bool) -> String {
+        if sibling_on_left {
+            hash_node(sibling, root)
+        } else {
+            hash_node(root, sibling)
         }
     }
 
-    #[derive(Debug)]
-    pub struct MerkleTree {
-        pub(crate) leaves: Vec<String>,
-        pub(crate) root_hash: String,
-    }
+    // a hash function the tree can be built with, returning the raw digest bytes
+    pub trait MerkleHasher: fmt::Debug + Send + Sync {
+        fn digest(&self, input: &[u8]) -> Vec<u8>;
 
-    #[derive(Debug)]
-    pub struct MerkleProof {
-        element: String,       // element for which we want to prove inclusion
-        siblings: Vec<String>, // path of siblings from the element up to the root
-        directions: Vec<bool>, // signal if the sibling at the same index is on the left or right
+        // identifies the hash function when comparing configurations, so two hashers with the
+        // same name must produce the same digests
+        fn name(&self) -> &str {
+            std::any::type_name::<Self>()
+        }
     }
 
-    #[allow(dead_code)]
-    #[derive(Debug)]
-    pub struct MerkleAggregateProof {
-        elements: Vec<String>, // range of elements for which we want to prove inclusion, in left-to-right order as present in the tree
-        siblings: Vec<String>, // path of siblings from the elements up to the last level of siblings necessary to generate the remainder up to the root
-        directions: Vec<bool>, // signal if the siblings at the same depth are on the left
+    // notified of every digest computed through a `TreeConfig` it is registered with, during
+    // construction and verification alike, e.g. to keep an audit log of the hashing done
+    pub trait HashObserver: fmt::Debug + Send + Sync {
+        fn on_hash(&self, input_len: usize);
     }
 
-    // return the root hash of the merkle tree
-    pub fn get_root(ref_tree: &MerkleTree) -> String {
-        ref_tree.root_hash.to_owned()
-    }
+    // the SHA-256 hasher backing `hash_leaf` and `hash_node`, used unless configured otherwise
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Sha256Hasher;
 
-    // create a merkle tree from a list of elements
-    // the tree should have the minimum height needed to contain all elements
-    // empty slots should be filled with an empty string
-    pub fn create_merkle_tree(elements: &Vec<String>) -> Result<MerkleTree, String> {
-        let mut leaves = elements.to_owned();
+    impl MerkleHasher for Sha256Hasher {
+        fn digest(&self, input: &[u8]) -> Vec<u8> {
+            let mut hasher = Sha256::new();
+            let mut output = vec![0; hasher.output_bytes()];
+            hasher.input(input);
+            hasher.result(&mut output);
+            output
+        }
+    }
 
-        leaf_pairwise_check(&mut leaves);
+    // SHA-256 in the shape `merkle_core` takes its hash function
+    pub fn sha256(input: &[u8]) -> [u8; 32] {
+        let mut output = [0; 32];
+        let mut hasher = Sha256::new();
+        hasher.input(input);
+        hasher.result(&mut output);
+        output
+    }
 
-        let mut nodes: Vec<MerkleNode> = leaves.iter().map(|e| e.to_owned().into()).collect::<_>();
+    // a `merkle_core` hash function, for building trees with the same hash as a core tree
+    #[derive(Clone, Copy, Debug)]
+    pub struct CoreHasher(pub crate::merkle_core::HashFn);
 
-        while nodes.len() > 1 {
-            nodes = generate_parent_row(nodes);
+    impl MerkleHasher for CoreHasher {
+        fn digest(&self, input: &[u8]) -> Vec<u8> {
+            (self.0)(input).to_vec()
         }
+    }
 
-        let root_hash = nodes[0].value.to_owned();
+    // Keccak-256 as exposed by Solidity's `keccak256`, for commitments verified on Ethereum
+    //
+    // nodes are still hashed over the concatenated lowercase hex of their children, so the
+    // matching Solidity reference for a node is:
+    //
+    //     keccak256(abi.encodePacked(toHexString(left), toHexString(right)))
+    //
+    // where `toHexString` renders the `bytes32` digest as 64 lowercase hex characters without
+    // a `0x` prefix, and a leaf is `keccak256(bytes(element))`.
+    #[cfg(feature = "keccak")]
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Keccak256Hasher;
 
-        Ok(MerkleTree { leaves, root_hash })
-    }
+    #[cfg(feature = "keccak")]
+    impl MerkleHasher for Keccak256Hasher {
+        fn digest(&self, input: &[u8]) -> Vec<u8> {
+            use tiny_keccak::{Hasher, Keccak};
 
-    fn leaf_pairwise_check(leaves: &mut Vec<String>) {
-        if leaves.len() % 2 == 1 {
-            leaves.push(String::default());
+            let mut hasher = Keccak::v256();
+            let mut output = vec![0; 32];
+            hasher.update(input);
+            hasher.finalize(&mut output);
+            output
         }
     }
 
-    fn generate_parent(left: MerkleNode, right: MerkleNode) -> MerkleNode {
-        MerkleNode {
-            value: hash_node(&left.value, &right.value),
-            left: Some(Box::new(left)),
-            right: Some(Box::new(right)),
+    // BLAKE3 with its default 32 byte output, a faster alternative to SHA-256 for new trees
+    #[cfg(feature = "blake3")]
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Blake3Hasher;
+
+    #[cfg(feature = "blake3")]
+    impl MerkleHasher for Blake3Hasher {
+        fn digest(&self, input: &[u8]) -> Vec<u8> {
+            blake3::hash(input).as_bytes().to_vec()
         }
     }
 
-    fn generate_parent_row(nodes: Vec<MerkleNode>) -> Vec<MerkleNode> {
-        let mut parents: Vec<MerkleNode> = Vec::new();
+    // the hashing scheme a tree is built with, carried along by its proofs so they can be
+    // verified the same way
+    #[derive(Clone, Debug)]
+    pub struct TreeConfig {
+        hasher: Arc<dyn MerkleHasher>,
+        domain: String, // namespace mixed into every leaf hash, binding proofs to one tree
+        salt: Vec<u8>, // bytes hashed ahead of every leaf, against brute-forcing low-entropy leaves
+        byte_concat: bool, // hash nodes over their children's raw digest bytes instead of hex
+        node_separator: bool, // put a ':' between the hex of the two children of a node
+        padding: String, // element appended to fill out the leaves
+        odd_handling: OddHandling, // how the last node of an odd row is paired
+        fixed_depth: Option<usize>, // pad the leaves up to exactly 2^depth rather than to an even count
+        encoding: Encoding,         // text encoding of every digest, including the root
+        normalize: bool,            // hash leaves in Unicode normalization form C
+        leaf_transforms: Vec<LeafTransform>, // canonicalizations applied in order to leaves before hashing
+        max_leaves: Option<usize>,           // refuse to build a tree over more elements than this
+        max_depth: Option<usize>,            // refuse to build a tree taller than this
+        observer: Option<Arc<dyn HashObserver>>, // told of every digest computed under this configuration
+    }
 
-        nodes
-            .chunks_exact(2)
-            .for_each(|pair| parents.push(generate_parent(pair[0].to_owned(), pair[1].to_owned())));
+    // configurations are equal when they hash identically, with hashers compared by `name`, so
+    // the construction limits and any observer are left out
+    impl PartialEq for TreeConfig {
+        fn eq(&self, other: &Self) -> bool {
+            self.hasher.name() == other.hasher.name()
+                && self.domain == other.domain
+                && self.salt == other.salt
+                && self.byte_concat == other.byte_concat
+                && self.node_separator == other.node_separator
+                && self.padding == other.padding
+                && self.odd_handling == other.odd_handling
+                && self.fixed_depth == other.fixed_depth
+                && self.encoding == other.encoding
+                && self.normalize == other.normalize
+                && self.leaf_transforms == other.leaf_transforms
+        }
+    }
 
-        nodes
-            .chunks_exact(2)
-            .remainder()
-            .iter()
-            .for_each(|node| parents.push(generate_parent(node.to_owned(), MerkleNode::default())));
+    impl Eq for TreeConfig {}
 
-        parents
+    impl std::hash::Hash for TreeConfig {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.hasher.name().hash(state);
+            self.domain.hash(state);
+            self.salt.hash(state);
+            self.byte_concat.hash(state);
+            self.node_separator.hash(state);
+            self.padding.hash(state);
+            self.odd_handling.hash(state);
+            self.fixed_depth.hash(state);
+            self.encoding.hash(state);
+            self.normalize.hash(state);
+            self.leaf_transforms.hash(state);
+        }
     }
 
-    // return a merkle proof of the inclusion of element at the given index
-    //
-    // example:
-    // proof for index 2 (marked with E), return the nodes marked `*` at each layer.
-    //
-    // tree:
-    // d0:                                   [ R ]
-    // d1:                [*]                                     [*]
-    // d2:      [*]                 [*]                 [ ]                 [ ]
-    // d3: [ ]       [ ]       [E]       [*]       [ ]       [ ]       [ ]       [ ]
-    //
-    // proof:
-    // element    = E
-    // siblings   = [d3-3, d2-0, d1-1]
-    // directions = [false, true, false]
-    pub fn get_proof(ref_tree: &MerkleTree, index: usize) -> Result<MerkleProof, String> {
-        if index >= ref_tree.leaves.len() {
-            return Err("Index of the target element is out of bounds for this tree".to_string());
+    impl Default for TreeConfig {
+        fn default() -> Self {
+            TreeConfig {
+                hasher: Arc::new(Sha256Hasher),
+                domain: String::default(),
+                salt: Vec::new(),
+                byte_concat: false,
+                node_separator: false,
+                padding: String::default(),
+                fixed_depth: None,
+                odd_handling: OddHandling::default(),
+                encoding: Encoding::default(),
+                normalize: false,
+                leaf_transforms: Vec::new(),
+                max_leaves: None,
+                max_depth: None,
+                observer: None,
+            }
         }
+    }
 
-        let element = ref_tree.leaves[index].to_owned();
-        let mut siblings: Vec<String> = Vec::new();
-        let mut directions: Vec<bool> = Vec::new();
+    // how digests are rendered as strings
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub enum Encoding {
+        // lowercase hex, as `hasher` produces
+        #[default]
+        Hex,
+        // standard base64 with padding
+        Base64,
+    }
 
-        let mut current_row: Vec<MerkleNode> = ref_tree
-            .leaves
-            .to_owned()
-            .iter()
-            .map(|leaf| leaf.to_owned().into())
-            .collect::<_>();
-        let mut current_node = current_row[index].to_owned();
+    // a canonicalization of leaves before they are hashed, such as for case-insensitive
+    // membership of email addresses
+    #[derive(Clone)]
+    pub enum LeafTransform {
+        // strip leading and trailing whitespace
+        TrimWhitespace,
+        // lowercase every character, per Unicode
+        Lowercase,
+        // any other transform, identified by its name when comparing configurations, so two
+        // custom transforms with the same name must map leaves the same way
+        Custom(&'static str, Arc<dyn Fn(&str) -> String + Send + Sync>),
+    }
 
-        while current_row.len() > 1 {
-            let current_index = current_row
-					.iter()
-					.position(|node| node.value.eq(&current_node.value))
-					.expect(
-							"Should have been able to locate the generated node in the row\
-                             Check the node and row generators at the bottom of the loop to verify."
-                    );
-            let sibling_is_left_child = !current_index % 2 == 0;
-
-            if sibling_is_left_child {
-                siblings.push(current_row[current_index - 1].value.to_owned());
-            } else {
-                siblings.push(current_row[current_index + 1].value.to_owned());
+    impl LeafTransform {
+        fn apply(&self, leaf: &str) -> String {
+            match self {
+                LeafTransform::TrimWhitespace => leaf.trim().to_string(),
+                LeafTransform::Lowercase => leaf.to_lowercase(),
+                LeafTransform::Custom(_, transform) => transform(leaf),
             }
+        }
 
-            directions.push(sibling_is_left_child);
+        fn name(&self) -> &str {
+            match self {
+                LeafTransform::TrimWhitespace => "TrimWhitespace",
+                LeafTransform::Lowercase => "Lowercase",
+                LeafTransform::Custom(name, _) => name,
+            }
+        }
+    }
 
-            current_row = generate_parent_row(current_row);
-            current_node = current_row[current_index / 2].to_owned();
+    impl fmt::Debug for LeafTransform {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                LeafTransform::Custom(name, _) => f.debug_tuple("Custom").field(name).finish(),
+                _ => f.write_str(self.name()),
+            }
         }
+    }
 
-        Ok(MerkleProof {
-            element,
-            siblings,
-            directions,
-        })
+    impl PartialEq for LeafTransform {
+        fn eq(&self, other: &Self) -> bool {
+            self.name() == other.name()
+        }
     }
 
-    // verify a merkle sub-tree against a known root
-    pub fn verify_proof(root: String, proof: &MerkleProof) -> bool {
-        let mut current_hash = hash_leaf(&proof.element);
+    impl Eq for LeafTransform {}
 
-        proof
-            .siblings
-            .iter()
-            .zip(proof.directions.iter())
-            .for_each(|(sibling, is_left_child)| {
-                current_hash = if *is_left_child {
-                    hash_node(sibling, &current_hash)
-                } else {
-                    hash_node(&current_hash, sibling)
-                };
-            });
+    impl std::hash::Hash for LeafTransform {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.name().hash(state);
+        }
+    }
 
-        current_hash.eq(&root)
+    // how the last node of a row with an odd number of nodes gets a sibling
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub enum OddHandling {
+        // pad the leaves with the padding element, and pair internal nodes with an empty node
+        #[default]
+        PadEmpty,
+        // pair the last leaf or node with a copy of itself, as Bitcoin does
+        //
+        // beware that this makes the tree malleable (CVE-2012-2459): [a, b, c] and [a, b, c, c]
+        // produce the same root, so the root alone doesn't commit to the number of elements
+        DuplicateLast,
     }
 
-    // ** BONUS (optional - easy) **
-    // Updates the Merkle tree (from leaf to root) to include the new element at index.
-    // For simplicity, the index must be within the bounds of the original vector size.
-    // If it is not, return an error.
-    pub fn update_element(
-        tree: MerkleTree,
-        index: usize,
-        element: &str,
-    ) -> Result<MerkleTree, String> {
-        if index >= tree.leaves.len() {
-            return Err("Index of the target element is out of bounds for this tree".to_string());
+    impl TreeConfig {
+        pub fn with_hasher<H: MerkleHasher + 'static>(mut self, hasher: H) -> Self {
+            self.hasher = Arc::new(hasher);
+            self
         }
 
-        let mut elements = tree.leaves;
-        elements.retain(|e| !e.is_empty());
-        elements.insert(index, element.to_string());
-
-        create_merkle_tree(&elements)
-    }
+        // separate the leaves of this tree from those of trees in other domains, so a proof
+        // generated for one cannot be replayed against the root of another
+        pub fn with_domain(mut self, domain: &str) -> Self {
+            self.domain = domain.to_string();
+            self
+        }
 
-    // ** BONUS (optional - hard) **
-    // Generates a Merkle proof of the inclusion of contiguous elements,
-    // starting at startIndex (inclusive) and ending at endIndex (exclusive).
-    // If the indexes are out of bounds or startIndex >= endIndex, an error is returned.
-    //
-    // Note: modify the method signature to return your proof type.
-    // Implement a separate verify_aggregate_proof for this type.
-    //
-    // The aggregated proof size should generally be smaller than
-    // that of the naive approach (calling GetProof for every index).
-    pub fn get_aggregate_proof(
-        ref_tree: &MerkleTree,
-        start_index: usize,
-        end_index: usize,
-    ) -> Result<MerkleAggregateProof, String> {
-        if start_index >= end_index || end_index >= ref_tree.leaves.len() {
-            return Err(
-                "Invalid range indices for the target elements.\
-                 Ensure your start and end both fall within the leaves vector for the given tree."
-                    .to_string(),
-            );
+        // hash every leaf as `hash(salt || leaf)`, so that leaves drawn from a small set of
+        // values, such as phone numbers, can't be recovered from their hashes with a table
+        // precomputed without the salt
+        //
+        // proofs carry the salt along with the rest of the configuration; anyone who is not
+        // meant to brute-force the leaves must not be handed it
+        pub fn with_salt(mut self, salt: &[u8]) -> Self {
+            self.salt = salt.to_vec();
+            self
         }
 
-        let elements = ref_tree.leaves[start_index..end_index].to_vec();
-        let mut siblings: Vec<String> = Vec::new();
-        let mut directions: Vec<bool> = Vec::new();
+        // fill out the leaves with this element instead of an empty string
+        pub fn with_padding(mut self, padding: &str) -> Self {
+            self.padding = padding.to_string();
+            self
+        }
 
-        let mut current_row: Vec<MerkleNode> = ref_tree
-            .leaves
-            .to_owned()
-            .iter()
-            .map(|leaf| leaf.to_owned().into())
-            .collect::<_>();
-        let mut current_start = start_index;
-        let mut current_end = end_index - 1;
+        // pad the leaves up to 2^depth so every proof has exactly `depth` siblings, at the cost
+        // of rejecting anything with more than 2^depth elements
+        pub fn with_fixed_depth(mut self, depth: usize) -> Self {
+            self.fixed_depth = Some(depth);
+            self
+        }
 
-        while current_start != 0 && current_end != (current_row.len() - 1) {
-            let start_sibling_is_left_child = !current_start % 2 == 0;
-            let end_sibling_is_right_child = !current_end % 2 == 1;
+        // refuse to build a tree over more than `max_leaves` elements, checked before anything
+        // is allocated for it, to guard against an accidentally enormous input
+        pub fn with_max_leaves(mut self, max_leaves: usize) -> Self {
+            self.max_leaves = Some(max_leaves);
+            self
+        }
 
-            if start_sibling_is_left_child {
-                siblings.push(current_row[current_start - 1].value.to_owned());
-            } else {
-                siblings.push(MerkleNode::default().value.to_owned())
-            }
+        // refuse to build a tree with more than `max_depth` levels above its leaves, checked
+        // before anything is built, for protocols that can't verify proofs longer than that
+        pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+            self.max_depth = Some(max_depth);
+            self
+        }
 
-            directions.push(start_sibling_is_left_child);
+        // pair the last node of odd rows according to the given strategy
+        pub fn with_odd_handling(mut self, odd_handling: OddHandling) -> Self {
+            self.odd_handling = odd_handling;
+            self
+        }
 
-            if end_sibling_is_right_child {
-                siblings.push(current_row[current_end + 1].value.to_owned());
-            } else {
-                siblings.push(MerkleNode::default().value.to_owned())
-            }
+        // render every digest of the tree, its root included, in the given encoding
+        //
+        // a tree and its proofs have to agree on the encoding, since node hashes are computed
+        // over the encoded digests of their children
+        pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+            self.encoding = encoding;
+            self
+        }
 
-            directions.push(end_sibling_is_right_child);
+        // hash every leaf in Unicode normalization form C, so that composed and decomposed
+        // spellings of the same text (such as "\u{e9}" and "e\u{301}") are the same element
+        //
+        // only the hashes are affected, the leaves themselves are kept as given; this changes the
+        // root of any tree with non-normalized leaves, so it has to be opted into
+        #[cfg(feature = "normalization")]
+        pub fn with_normalization(mut self, enabled: bool) -> Self {
+            self.normalize = enabled;
+            self
+        }
 
-            current_row = generate_parent_row(current_row);
-            current_start /= 2;
-            current_end /= 2;
+        // apply the transform to every leaf before it is hashed, after any added before it
+        //
+        // as with normalization the leaves are kept as given and only their hashes change, so
+        // proofs verify for any spelling that transforms to the same leaf; the root changes
+        // for any leaf the transform doesn't leave as-is, so it has to be opted into
+        pub fn with_leaf_transform(mut self, transform: LeafTransform) -> Self {
+            self.leaf_transforms.push(transform);
+            self
         }
 
-        Ok(MerkleAggregateProof {
-            elements,
-            siblings,
-            directions,
-        })
-    }
+        // notify the observer of every digest computed under this configuration, which its
+        // proofs carry along, so verifying them is observed too
+        pub fn with_hash_observer(mut self, observer: Arc<dyn HashObserver>) -> Self {
+            self.observer = Some(observer);
+            self
+        }
 
-    pub fn verify_aggregate_proof(root: String, proof: &MerkleAggregateProof) -> bool {
-        let mut current_row = proof
-            .elements
-            .iter()
-            .map(|leaf| leaf.to_owned().into())
-            .collect::<Vec<_>>();
+        // `hasher` under this configuration
+        pub fn hasher(&self, input: &str) -> String {
+            self.digest(input.as_bytes())
+        }
 
-        let proof_siblings = proof
-            .siblings
-            .iter()
-            .zip(proof.directions.iter())
-            .collect::<Vec<_>>();
+        // `hash_leaf` under this configuration
+        //
+        // a non-empty domain is prefixed to the leaf along with its length, so that the
+        // boundary between the two can't be shifted to forge a leaf in another domain
+        pub fn hash_leaf(&self, leaf: &str) -> String {
+            self.hash_weighted_leaf(leaf, None)
+        }
 
-        for chunk in proof_siblings.chunks(2) {
-            println!("current row: {current_row:#?}");
-            println!("chunk: {chunk:#?}");
-            let (start_sibling, start_is_left_child) = chunk[0];
-            let (end_sibling, end_is_right_child) = chunk[1];
+        // `hash_leaf` over the leaf followed by the little-endian bytes of its weight, if it has
+        // one, so that a weighted tree commits to both
+        pub fn hash_weighted_leaf(&self, leaf: &str, weight: Option<u64>) -> String {
+            let mut input = self.leaf_prefix();
+            input.extend_from_slice(self.normalized(leaf).as_bytes());
 
-            if *start_is_left_child {
-                current_row.insert(
-                    0,
-                    MerkleNode {
-                        value: start_sibling.to_owned(),
-                        left: None,
-                        right: None,
-                    },
-                );
+            if let Some(weight) = weight {
+                input.extend_from_slice(&weight.to_le_bytes());
             }
 
-            if *end_is_right_child {
-                current_row.push(MerkleNode {
-                    value: end_sibling.to_owned(),
-                    left: None,
-                    right: None,
-                });
+            self.digest(&input)
+        }
+
+        // `hash_leaf` over a key-value pair, as `len(key) || key || len(value) || value` with
+        // little-endian u64 lengths, so that no other split of the same bytes hashes the same
+        pub fn hash_kv_leaf(&self, key: &str, value: &str) -> String {
+            let mut input = self.leaf_prefix();
+
+            for part in [key, value] {
+                let part = self.normalized(part);
+                input.extend_from_slice(&(part.len() as u64).to_le_bytes());
+                input.extend_from_slice(part.as_bytes());
             }
 
-            current_row = generate_parent_row(current_row);
+            self.digest(&input)
         }
 
-        while current_row.len() > 1 {
-            println!("current row: {current_row:#?}");
-            current_row = generate_parent_row(current_row);
-        }
+        fn leaf_prefix(&self) -> Vec<u8> {
+            let mut prefix = Vec::new();
 
-        println!("root: {current_row:#?}");
-        current_row[0].value.eq(&root)
-    }
-}
+            if !self.domain.is_empty() {
+                prefix.extend_from_slice(&(self.domain.len() as u64).to_le_bytes());
+                prefix.extend_from_slice(self.domain.as_bytes());
+            }
 
-#[cfg(test)]
-mod validations {
-    use crate::merkle_tree::*;
+            prefix.extend_from_slice(&self.salt);
 
-    const TEST_ELEMENTS: [&str; 3] = ["some", "test", "elements"];
-    const MORE_TEST_ELEMENTS: [&str; 4] = ["some", "more", "test", "elements"];
-    const EVEN_MORE_TEST_ELEMENTS: [&str; 5] = ["some", "more", "valid", "test", "elements"];
-    const YET_MORE_TEST_ELEMENTS: [&str; 6] = ["some", "more", "valid", "test", "elements", "too"];
-    const LOTS_MORE_TEST_ELEMENTS: [&str; 7] =
-        ["some", "more", "valid", "test", "elements", "to", "use"];
-    const INCREASINGLY_MORE_TEST_ELEMENTS: [&str; 8] = [
-        "some", "more", "valid", "test", "elements", "to", "use", "again",
-    ];
-    const INVALID_HASH: &str = "not_a_valid_hash";
-    const VERIFY_PROOF_FAILED: bool = false;
+            prefix
+        }
 
-    fn get_test_tree(input: Vec<&str>) -> MerkleTree {
-        let elements = input.iter().map(|s| s.to_string()).collect::<Vec<_>>();
-        create_merkle_tree(&elements)
-            .expect("Should have received a valid tree given const test inputs")
-    }
+        // hash internal nodes over the 64 raw bytes of their children's digests rather than
+        // their 128 hex characters, as most other merkle tree implementations do
+        //
+        // this changes every root above the leaves, so trees built either way are not comparable
+        pub fn with_byte_concat(mut self, enabled: bool) -> Self {
+            self.byte_concat = enabled;
+            self
+        }
 
-    fn get_expected_root_hash(input: Vec<&str>) -> String {
-        let mut leaves = input;
-        if leaves.len() % 2 == 1 {
-            leaves.push("");
+        // hash internal nodes over `{left}:{right}` rather than `{left}{right}`, so the input of
+        // a node can't be read as some other split of the same characters into two children
+        //
+        // like `with_byte_concat` this changes every root above the leaves, and has no effect
+        // when byte concatenation is enabled, since raw digests already have a fixed width
+        pub fn with_node_separator(mut self, enabled: bool) -> Self {
+            self.node_separator = enabled;
+            self
         }
 
-        let mut nodes: Vec<String> = leaves.iter().map(|e| hash_leaf(e)).collect::<_>();
+        // `hash_node` under this configuration
+        pub fn hash_node(&self, left: &str, right: &str) -> String {
+            self.hash_children(&[left, right])
+        }
 
-        while nodes.len() > 1 {
-            let head: Vec<String> = nodes
-                .chunks_exact(2)
-                .map(|pair| hash_node(&pair[0], &pair[1]))
-                .collect::<_>();
+        // `hash_node` generalized to any number of children, concatenated in order
+        pub fn hash_children(&self, children: &[&str]) -> String {
+            if !self.byte_concat {
+                let separator = if self.node_separator { ":" } else { "" };
+                return self.digest(children.join(separator).as_bytes());
+            }
 
-            let tail: Vec<String> = nodes
-                .chunks_exact(2)
-                .remainder()
+            let input = children
                 .iter()
-                .map(|s| hash_node(s, ""))
-                .collect::<_>();
+                .flat_map(|child| self.decode_digest(child))
+                .collect::<Vec<_>>();
 
-            nodes = head.into_iter().chain(tail.into_iter()).collect::<Vec<_>>();
+            self.digest(&input)
         }
 
-        nodes[0].to_owned()
-    }
+        fn normalized<'a>(&self, leaf: &'a str) -> Cow<'a, str> {
+            let leaf = self
+                .leaf_transforms
+                .iter()
+                .fold(Cow::Borrowed(leaf), |leaf, transform| {
+                    Cow::Owned(transform.apply(&leaf))
+                });
 
-    #[test]
-    fn getting_root_hashes() {
-        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
-        let expected_root_hash = mt.root_hash.to_owned();
+            #[cfg(feature = "normalization")]
+            if self.normalize {
+                use unicode_normalization::UnicodeNormalization;
+                return Cow::Owned(leaf.nfc().collect());
+            }
 
-        assert_eq!(get_root(&mt), expected_root_hash);
-    }
+            leaf
+        }
 
-    #[test]
-    fn verifying_proofs() {
+        // the sibling given to `last`, the final node of an odd row
+        fn odd_sibling(&self, last: &MerkleNode) -> MerkleNode {
+            match self.odd_handling {
+                OddHandling::PadEmpty => MerkleNode::default(),
+                OddHandling::DuplicateLast => last.to_owned(),
+            }
+        }
+
+        fn odd_sibling_hash(&self, last: &str) -> String {
+            match self.odd_handling {
+                OddHandling::PadEmpty => MerkleNode::default().value,
+                OddHandling::DuplicateLast => last.to_string(),
+            }
+        }
+
+        fn digest(&self, input: &[u8]) -> String {
+            #[cfg(test)]
+            HASH_COUNT.with(|count| count.set(count.get() + 1));
+
+            if let Some(observer) = &self.observer {
+                observer.on_hash(input.len());
+            }
+
+            self.encode_digest(&self.hasher.digest(input))
+        }
+
+        fn encode_digest(&self, digest: &[u8]) -> String {
+            match self.encoding {
+                Encoding::Hex => hex::encode(digest),
+                Encoding::Base64 => BASE64.encode(digest),
+            }
+        }
+
+        // raw bytes of an encoded digest; anything that doesn't decode (such as a tampered
+        // sibling) is hashed as-is, which can only ever lead to a root mismatch
+        fn decode_digest(&self, digest: &str) -> Vec<u8> {
+            let decoded = match self.encoding {
+                Encoding::Hex => hex::decode(digest).ok(),
+                Encoding::Base64 => BASE64.decode(digest).ok(),
+            };
+
+            decoded.unwrap_or_else(|| digest.as_bytes().to_vec())
+        }
+    }
+
+    // number of digests computed through a `TreeConfig` on the current thread, for tests that
+    // assert on how much hashing an operation does
+    #[cfg(test)]
+    thread_local! {
+        pub(crate) static HASH_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    // step-by-step construction of a tree, for when more than one option departs from the
+    // defaults of `create_merkle_tree`
+    //
+    // let tree = MerkleTreeBuilder::new()
+    //     .with_domain("registry")
+    //     .fixed_depth(4)
+    //     .build(&elements)?;
+    #[derive(Clone, Debug, Default)]
+    pub struct MerkleTreeBuilder {
+        config: TreeConfig,
+    }
+
+    impl MerkleTreeBuilder {
+        pub fn new() -> Self {
+            MerkleTreeBuilder::default()
+        }
+
+        pub fn with_hasher<H: MerkleHasher + 'static>(mut self, hasher: H) -> Self {
+            self.config = self.config.with_hasher(hasher);
+            self
+        }
+
+        pub fn with_padding(mut self, padding: &str) -> Self {
+            self.config = self.config.with_padding(padding);
+            self
+        }
+
+        pub fn with_domain(mut self, domain: &str) -> Self {
+            self.config = self.config.with_domain(domain);
+            self
+        }
+
+        pub fn with_salt(mut self, salt: &[u8]) -> Self {
+            self.config = self.config.with_salt(salt);
+            self
+        }
+
+        pub fn with_byte_concat(mut self, enabled: bool) -> Self {
+            self.config = self.config.with_byte_concat(enabled);
+            self
+        }
+
+        pub fn with_node_separator(mut self, enabled: bool) -> Self {
+            self.config = self.config.with_node_separator(enabled);
+            self
+        }
+
+        pub fn fixed_depth(mut self, depth: usize) -> Self {
+            self.config = self.config.with_fixed_depth(depth);
+            self
+        }
+
+        pub fn with_odd_handling(mut self, odd_handling: OddHandling) -> Self {
+            self.config = self.config.with_odd_handling(odd_handling);
+            self
+        }
+
+        pub fn max_leaves(mut self, max_leaves: usize) -> Self {
+            self.config = self.config.with_max_leaves(max_leaves);
+            self
+        }
+
+        pub fn max_depth(mut self, max_depth: usize) -> Self {
+            self.config = self.config.with_max_depth(max_depth);
+            self
+        }
+
+        pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+            self.config = self.config.with_encoding(encoding);
+            self
+        }
+
+        #[cfg(feature = "normalization")]
+        pub fn with_normalization(mut self, enabled: bool) -> Self {
+            self.config = self.config.with_normalization(enabled);
+            self
+        }
+
+        pub fn with_leaf_transform(mut self, transform: LeafTransform) -> Self {
+            self.config = self.config.with_leaf_transform(transform);
+            self
+        }
+
+        pub fn with_hash_observer(mut self, observer: Arc<dyn HashObserver>) -> Self {
+            self.config = self.config.with_hash_observer(observer);
+            self
+        }
+
+        pub fn build(self, elements: &[String]) -> Result<MerkleTree, String> {
+            create_merkle_tree_with_config(elements, self.config)
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct MerkleNode {
+        value: String,
+        left: Option<Box<MerkleNode>>,
+        right: Option<Box<MerkleNode>>,
+    }
+
+    impl From<String> for MerkleNode {
+        fn from(value: String) -> Self {
+            MerkleNode {
+                value: hash_leaf(&value),
+                left: None,
+                right: None,
+            }
+        }
+    }
+
+    impl MerkleNode {
+        // hash of this node
+        pub fn value(&self) -> &str {
+            &self.value
+        }
+
+        // left child of this node, if it is an internal node
+        pub fn left(&self) -> Option<&MerkleNode> {
+            self.left.as_deref()
+        }
+
+        // right child of this node, if it is an internal node
+        pub fn right(&self) -> Option<&MerkleNode> {
+            self.right.as_deref()
+        }
+
+        fn leaf(value: &str, config: &TreeConfig) -> Self {
+            MerkleNode::weighted_leaf(value, None, config)
+        }
+
+        fn weighted_leaf(value: &str, weight: Option<u64>, config: &TreeConfig) -> Self {
+            MerkleNode {
+                value: config.hash_weighted_leaf(value, weight),
+                left: None,
+                right: None,
+            }
+        }
+    }
+
+    // a tree is never modified once built, other than through `&mut` by `update_element_fast`,
+    // so it is `Send + Sync` and any number of threads can generate proofs from a shared
+    // `Arc<MerkleTree>` without locking
+    #[derive(Debug)]
+    pub struct MerkleTree {
+        pub(crate) leaves: Vec<String>,
+        pub(crate) root_hash: String,
+        pub(crate) config: TreeConfig,
+        pub(crate) element_count: usize, // number of leaves supplied by the caller, before padding
+        pub(crate) levels: Vec<Vec<String>>, // node hashes of every row, from the leaves up to the root
+        pub(crate) weights: Option<Vec<u64>>, // weight of every leaf, for trees built by `create_weighted_merkle_tree`
+    }
+
+    impl MerkleTree {
+        // all leaves of the tree, including any padding appended during construction
+        pub fn leaves(&self) -> &[String] {
+            &self.leaves
+        }
+
+        // number of leaves that hold a caller-supplied element rather than padding
+        pub fn real_leaf_count(&self) -> usize {
+            self.element_count
+        }
+
+        // a fingerprint of the tree, `hash(root || element_count.to_le_bytes())`, which unlike
+        // the root tells apart trees whose padding makes their leaves the same, such as
+        // ["a", "b", "c"] and ["a", "b", "c", ""], so it is safe to use as a map or cache key
+        pub fn tree_id(&self) -> String {
+            let mut input = self.root_hash.as_bytes().to_vec();
+            input.extend_from_slice(&(self.element_count as u64).to_le_bytes());
+
+            self.config.digest(&input)
+        }
+
+        // weight of the leaf at `index`, if this is a weighted tree
+        pub fn weight(&self, index: usize) -> Option<u64> {
+            self.weights.as_ref()?.get(index).copied()
+        }
+
+        // whether the leaf at `index` was appended to pad the tree rather than supplied
+        //
+        // `get_proof` refuses to prove a padding leaf, see `get_proof_allow_padding`
+        pub fn is_padding(&self, index: usize) -> bool {
+            index >= self.element_count && index < self.leaves.len()
+        }
+
+        // number of levels above the leaves, i.e. the number of siblings in each proof
+        pub fn height(&self) -> usize {
+            self.levels.len() - 1
+        }
+
+        // serialize the tree as its element count, its length-prefixed leaves and its root,
+        // followed by the weight of every leaf for a weighted tree
+        //
+        // the hashing configuration is not part of the encoding, so the tree has to be loaded
+        // with the same configuration it was built with (see `from_bytes_with_config`)
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let mut data = Vec::new();
+
+            write_u64(&mut data, self.element_count as u64);
+            write_u64(&mut data, self.leaves.len() as u64);
+            self.leaves
+                .iter()
+                .for_each(|leaf| write_str(&mut data, leaf));
+            write_str(&mut data, &self.root_hash);
+
+            if let Some(weights) = &self.weights {
+                weights
+                    .iter()
+                    .for_each(|&weight| write_u64(&mut data, weight));
+            }
+
+            data
+        }
+
+        // load a tree serialized with `to_bytes`, rebuilding it from its leaves and rejecting
+        // the data if the rebuilt root doesn't match the stored one
+        pub fn from_bytes(data: &[u8]) -> Result<MerkleTree, String> {
+            MerkleTree::from_bytes_with_config(data, TreeConfig::default())
+        }
+
+        // `from_bytes` for a tree built with a non-default configuration
+        pub fn from_bytes_with_config(
+            data: &[u8],
+            config: TreeConfig,
+        ) -> Result<MerkleTree, String> {
+            let mut reader = ByteReader::new(data);
+
+            let element_count = reader.read_u64()? as usize;
+            let leaf_count = reader.read_u64()? as usize;
+            let leaves = (0..leaf_count)
+                .map(|_| reader.read_str())
+                .collect::<Result<Vec<_>, _>>()?;
+            let root_hash = reader.read_str()?;
+            let weights = match reader.is_finished() {
+                true => None,
+                false => Some(
+                    (0..leaf_count)
+                        .map(|_| reader.read_u64())
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+            };
+            reader.finish()?;
+
+            if element_count > leaves.len() {
+                return Err("Serialized tree has more elements than leaves".to_string());
+            }
+
+            let tree = match &weights {
+                Some(weights) => create_weighted_merkle_tree_with_config(
+                    &leaves[..element_count]
+                        .iter()
+                        .cloned()
+                        .zip(weights[..element_count].iter().copied())
+                        .collect::<Vec<_>>(),
+                    config,
+                )?,
+                None => create_merkle_tree_with_config(&leaves[..element_count], config)?,
+            };
+
+            if tree.leaves != leaves || tree.weights != weights || tree.root_hash != root_hash {
+                return Err(
+                    "Serialized tree is corrupted, its leaves do not hash to its stored root"
+                        .to_string(),
+                );
+            }
+
+            Ok(tree)
+        }
+    }
+
+    fn write_u64(data: &mut Vec<u8>, value: u64) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_str(data: &mut Vec<u8>, value: &str) {
+        write_u64(data, value.len() as u64);
+        data.extend_from_slice(value.as_bytes());
+    }
+
+    // cursor over serialized data, failing on anything truncated or malformed
+    struct ByteReader<'a> {
+        data: &'a [u8],
+        offset: usize,
+    }
+
+    impl<'a> ByteReader<'a> {
+        fn new(data: &'a [u8]) -> Self {
+            ByteReader { data, offset: 0 }
+        }
+
+        fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+            let end = self
+                .offset
+                .checked_add(len)
+                .filter(|&end| end <= self.data.len())
+                .ok_or_else(|| "Serialized data is truncated".to_string())?;
+            let bytes = &self.data[self.offset..end];
+            self.offset = end;
+
+            Ok(bytes)
+        }
+
+        fn read_u64(&mut self) -> Result<u64, String> {
+            let bytes = self.read_bytes(8)?;
+
+            Ok(u64::from_le_bytes(
+                bytes.try_into().expect("read exactly 8 bytes"),
+            ))
+        }
+
+        fn read_u8(&mut self) -> Result<u8, String> {
+            Ok(self.read_bytes(1)?[0])
+        }
+
+        fn read_str(&mut self) -> Result<String, String> {
+            let len = self.read_u64()?;
+            let len =
+                usize::try_from(len).map_err(|_| "Serialized length is too large".to_string())?;
+
+            String::from_utf8(self.read_bytes(len)?.to_vec())
+                .map_err(|_| "Serialized string is not valid UTF-8".to_string())
+        }
+
+        fn is_finished(&self) -> bool {
+            self.offset == self.data.len()
+        }
+
+        fn finish(&self) -> Result<(), String> {
+            if self.offset != self.data.len() {
+                return Err("Serialized data has trailing bytes".to_string());
+            }
+
+            Ok(())
+        }
+    }
+
+    // renders the tree top-down as an indented outline of truncated hashes, one node per line,
+    // with the plaintext appended to each leaf, e.g. for a two element tree:
+    //
+    // 8ed3d7ca
+    //   a6b46dd0 "some"
+    //   9f86d081 "test"
+    impl fmt::Display for MerkleTree {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut nodes: Vec<MerkleNode> = self.levels[0]
+                .iter()
+                .map(|hash| MerkleNode {
+                    value: hash.to_owned(),
+                    left: None,
+                    right: None,
+                })
+                .collect::<_>();
+            let mut height = 0;
+
+            while nodes.len() > 1 {
+                nodes = generate_parent_row(nodes, &self.config);
+                height += 1;
+            }
+
+            write_node(f, &nodes[0], 0, height, &mut self.leaves.iter())
+        }
+    }
+
+    fn write_node(
+        f: &mut fmt::Formatter<'_>,
+        node: &MerkleNode,
+        depth: usize,
+        height: usize,
+        leaves: &mut std::slice::Iter<String>,
+    ) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let prefix = node.value.get(..8).unwrap_or(&node.value);
+
+        if depth == height {
+            let leaf = leaves.next().map(String::as_str).unwrap_or_default();
+            return writeln!(f, "{indent}{prefix} {leaf:?}");
+        }
+
+        match (&node.left, &node.right) {
+            (Some(left), Some(right)) => {
+                writeln!(f, "{indent}{prefix}")?;
+                write_node(f, left, depth + 1, height, leaves)?;
+
+                // a right child starting past the last leaf is the copy of its left sibling that
+                // `OddHandling::DuplicateLast` pairs with the last node of an odd row
+                match leaves.len() {
+                    0 => writeln!(f, "{indent}  (duplicate)"),
+                    _ => write_node(f, right, depth + 1, height, leaves),
+                }
+            }
+            // the empty node `generate_parent_row` pairs with the last node of an odd row
+            _ => writeln!(f, "{indent}(empty)"),
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    pub struct MerkleProof {
+        pub(crate) element: String, // element for which we want to prove inclusion
+        pub(crate) siblings: Vec<String>, // path of siblings from the element up to the root
+        pub(crate) directions: Vec<bool>, // signal if the sibling at the same index is on the left or right
+        pub(crate) config: TreeConfig,    // hashing scheme of the tree the proof was generated from
+        pub(crate) weight: Option<u64>, // weight folded into the element's leaf hash, for proofs from weighted trees
+        pub(crate) arity: usize, // children of every node on the path, with arity-1 siblings per level
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    pub struct MerkleAggregateProof {
+        pub(crate) elements: Vec<String>, // range of elements for which we want to prove inclusion, in left-to-right order as present in the tree
+        pub(crate) siblings: Vec<String>, // path of siblings from the elements up to the last level of siblings necessary to generate the remainder up to the root
+        pub(crate) directions: Vec<bool>, // signal if the siblings at the same depth are on the left
+        pub(crate) start_index: usize,    // leaf index of the first element
+        pub(crate) leaf_count: usize, // number of leaves in the tree, which fixes the length of every row
+        pub(crate) config: TreeConfig, // hashing scheme of the tree the proof was generated from
+    }
+
+    #[derive(Debug)]
+    pub struct SparseProof {
+        pub(crate) indices: Vec<usize>, // sorted, deduplicated leaf indices of the proven elements
+        pub(crate) elements: Vec<String>, // elements for which we want to prove inclusion, in the same order as the indices
+        pub(crate) leaf_count: usize, // number of leaves in the tree, which fixes the length of every row
+        pub(crate) siblings: Vec<String>, // hashes the verifier can't derive from the elements, bottom-up and left-to-right
+        pub(crate) config: TreeConfig,    // hashing scheme of the tree the proof was generated from
+    }
+
+    // version of the proof encoding written by `to_bytes`, bumped whenever the layout changes so
+    // that proofs serialized by an older version are rejected rather than misparsed
+    const PROOF_VERSION: u8 = 4;
+
+    impl MerkleProof {
+        // rebuild a proof from an element and a cached path of `(sibling, is_left)` pairs, from
+        // the leaf up, for light clients that keep paths but not the tree
+        pub fn from_path(element: String, path: Vec<(String, bool)>) -> MerkleProof {
+            MerkleProof::from_path_with_config(element, path, TreeConfig::default())
+        }
+
+        // `from_path` for a path from a tree built with a non-default configuration
+        pub fn from_path_with_config(
+            element: String,
+            path: Vec<(String, bool)>,
+            config: TreeConfig,
+        ) -> MerkleProof {
+            let (siblings, directions) = path.into_iter().unzip();
+
+            MerkleProof {
+                element,
+                siblings,
+                directions,
+                config,
+                weight: None,
+                arity: 2,
+            }
+        }
+
+        // the element the proof claims is part of the tree, only proven once the proof verifies
+        pub fn element(&self) -> &str {
+            &self.element
+        }
+
+        // the weight the proof claims for its element, if it is from a weighted tree
+        pub fn weight(&self) -> Option<u64> {
+            self.weight
+        }
+
+        // number of children of every node on the proof's path, 2 unless the proof was
+        // converted from a `KaryMerkleProof`
+        pub fn arity(&self) -> usize {
+            self.arity
+        }
+
+        // serialize the proof as a version byte, its element, its weight if it has one, its
+        // arity, and its siblings with their directions
+        //
+        // like trees, the hashing configuration is not part of the encoding
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let mut data = vec![PROOF_VERSION];
+
+            write_str(&mut data, &self.element);
+            match self.weight {
+                Some(weight) => {
+                    data.push(1);
+                    write_u64(&mut data, weight);
+                }
+                None => data.push(0),
+            }
+            write_u64(&mut data, self.arity as u64);
+            write_siblings(&mut data, &self.siblings, &self.directions);
+
+            data
+        }
+
+        // load a proof serialized with `to_bytes`
+        pub fn from_bytes(data: &[u8]) -> Result<MerkleProof, String> {
+            MerkleProof::from_bytes_with_config(data, TreeConfig::default())
+        }
+
+        // `from_bytes` for a proof from a tree built with a non-default configuration
+        pub fn from_bytes_with_config(
+            data: &[u8],
+            config: TreeConfig,
+        ) -> Result<MerkleProof, String> {
+            let mut reader = ByteReader::new(data);
+
+            read_proof_version(&mut reader)?;
+            let element = reader.read_str()?;
+            let weight = match reader.read_u8()? {
+                0 => None,
+                1 => Some(reader.read_u64()?),
+                _ => return Err("Serialized weight flag is not a boolean".to_string()),
+            };
+            let arity = read_arity(&mut reader)?;
+            let (siblings, directions) = read_siblings(&mut reader)?;
+            reader.finish()?;
+
+            Ok(MerkleProof {
+                element,
+                siblings,
+                directions,
+                config,
+                weight,
+                arity,
+            })
+        }
+    }
+
+    impl MerkleProof {
+        // a denser encoding than `to_bytes` for proofs over 32 byte digests: the length-prefixed
+        // element, the arity, the sibling count, a bitmap of the directions, a bitmap of the siblings that
+        // are empty nodes, then the raw 32 bytes of every other sibling, and finally the weight of
+        // the element if it has one
+        //
+        // fails if a sibling isn't a 32 byte digest in the encoding of the proof's configuration
+        pub fn encode(&self) -> Result<Vec<u8>, String> {
+            let mut data = Vec::new();
+
+            write_str(&mut data, &self.element);
+            write_u64(&mut data, self.arity as u64);
+            write_u64(&mut data, self.siblings.len() as u64);
+            data.extend(pack_bits(self.directions.iter().copied()));
+            data.extend(pack_bits(self.siblings.iter().map(String::is_empty)));
+
+            for sibling in self.siblings.iter().filter(|sibling| !sibling.is_empty()) {
+                let digest = self.config.decode_digest(sibling);
+
+                if digest.len() != 32 {
+                    return Err(format!(
+                        "Compact encoding only supports 32 byte digests, got {} bytes",
+                        digest.len()
+                    ));
+                }
+
+                data.extend(digest);
+            }
+
+            if let Some(weight) = self.weight {
+                write_u64(&mut data, weight);
+            }
+
+            Ok(data)
+        }
+
+        // load a proof written by `encode`
+        pub fn decode(data: &[u8]) -> Result<MerkleProof, String> {
+            MerkleProof::decode_with_config(data, TreeConfig::default())
+        }
+
+        // `decode` for a proof from a tree built with a non-default configuration
+        pub fn decode_with_config(data: &[u8], config: TreeConfig) -> Result<MerkleProof, String> {
+            let mut reader = ByteReader::new(data);
+
+            let element = reader.read_str()?;
+            let arity = read_arity(&mut reader)?;
+            let sibling_count = reader.read_u64()?;
+            let sibling_count = usize::try_from(sibling_count)
+                .map_err(|_| "Serialized length is too large".to_string())?;
+            let bitmap_len = sibling_count.div_ceil(8);
+            let directions = unpack_bits(reader.read_bytes(bitmap_len)?, sibling_count)?;
+            let empty = unpack_bits(reader.read_bytes(bitmap_len)?, sibling_count)?;
+
+            let siblings = empty
+                .iter()
+                .map(|&is_empty| match is_empty {
+                    true => Ok(MerkleNode::default().value),
+                    false => Ok(config.encode_digest(reader.read_bytes(32)?)),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let weight = match reader.is_finished() {
+                true => None,
+                false => Some(reader.read_u64()?),
+            };
+            reader.finish()?;
+
+            Ok(MerkleProof {
+                element,
+                siblings,
+                directions,
+                config,
+                weight,
+                arity,
+            })
+        }
+
+        // render the proof as a JSON object, with its weight only if it has one and its arity
+        // only if it isn't a binary proof:
+        //
+        // {"element":"...","siblings":["...",...],"directions":[true,...],"weight":3,"arity":4}
+        //
+        // built by hand so no serialization framework is needed to exchange proofs as JSON
+        pub fn to_json(&self) -> String {
+            let mut json = String::from("{\"element\":");
+            write_json_str(&mut json, &self.element);
+
+            json.push_str(",\"siblings\":[");
+            self.siblings.iter().enumerate().for_each(|(i, sibling)| {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_json_str(&mut json, sibling);
+            });
+
+            json.push_str("],\"directions\":[");
+            let directions = self
+                .directions
+                .iter()
+                .map(bool::to_string)
+                .collect::<Vec<_>>();
+            json.push_str(&directions.join(","));
+            json.push(']');
+
+            if let Some(weight) = self.weight {
+                json.push_str(&format!(",\"weight\":{weight}"));
+            }
+
+            if self.arity != 2 {
+                json.push_str(&format!(",\"arity\":{}", self.arity));
+            }
+
+            json.push('}');
+            json
+        }
+
+        // load a proof rendered with `to_json`
+        pub fn from_json(json: &str) -> Result<MerkleProof, String> {
+            MerkleProof::from_json_with_config(json, TreeConfig::default())
+        }
+
+        // `from_json` for a proof from a tree built with a non-default configuration
+        pub fn from_json_with_config(
+            json: &str,
+            config: TreeConfig,
+        ) -> Result<MerkleProof, String> {
+            let mut reader = JsonReader::new(json);
+            let (mut element, mut siblings, mut directions, mut weight) = (None, None, None, None);
+            let mut arity = None;
+
+            reader.expect('{')?;
+
+            if !reader.next_is('}') {
+                loop {
+                    let key = reader.read_str()?;
+                    reader.expect(':')?;
+
+                    match key.as_str() {
+                        "element" if element.is_none() => element = Some(reader.read_str()?),
+                        "siblings" if siblings.is_none() => {
+                            siblings = Some(reader.read_array(JsonReader::read_str)?)
+                        }
+                        "directions" if directions.is_none() => {
+                            directions = Some(reader.read_array(JsonReader::read_bool)?)
+                        }
+                        "weight" if weight.is_none() => weight = Some(reader.read_u64()?),
+                        "arity" if arity.is_none() => arity = Some(reader.read_u64()?),
+                        _ => return Err(format!("Unexpected or repeated key {key:?} in a proof")),
+                    }
+
+                    if reader.next_is('}') {
+                        break;
+                    }
+
+                    reader.expect(',')?;
+                }
+            }
+
+            reader.expect('}')?;
+            reader.finish()?;
+
+            let missing = |key: &str| format!("Missing key {key:?} in a proof");
+
+            Ok(MerkleProof {
+                element: element.ok_or_else(|| missing("element"))?,
+                siblings: siblings.ok_or_else(|| missing("siblings"))?,
+                directions: directions.ok_or_else(|| missing("directions"))?,
+                config,
+                weight,
+                arity: check_arity(arity.unwrap_or(2))?,
+            })
+        }
+    }
+
+    // append a JSON string literal, escaping quotes, backslashes and control characters
+    fn write_json_str(json: &mut String, value: &str) {
+        json.push('"');
+
+        value.chars().for_each(|c| match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        });
+
+        json.push('"');
+    }
+
+    // a cursor over the JSON `to_json` writes, skipping whitespace between tokens
+    struct JsonReader<'a> {
+        chars: std::iter::Peekable<std::str::Chars<'a>>,
+    }
+
+    impl<'a> JsonReader<'a> {
+        fn new(json: &'a str) -> Self {
+            JsonReader {
+                chars: json.chars().peekable(),
+            }
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+        }
+
+        fn next_is(&mut self, expected: char) -> bool {
+            self.skip_whitespace();
+            self.chars.peek() == Some(&expected)
+        }
+
+        fn expect(&mut self, expected: char) -> Result<(), String> {
+            self.skip_whitespace();
+
+            match self.chars.next() {
+                Some(c) if c == expected => Ok(()),
+                Some(c) => Err(format!(
+                    "Malformed JSON: expected {expected:?}, found {c:?}"
+                )),
+                None => Err(format!(
+                    "Malformed JSON: expected {expected:?}, found the end"
+                )),
+            }
+        }
+
+        fn read_str(&mut self) -> Result<String, String> {
+            self.expect('"')?;
+            let mut value = String::new();
+
+            loop {
+                match self.chars.next() {
+                    Some('"') => return Ok(value),
+                    Some('\\') => value.push(self.read_escape()?),
+                    Some(c) if c.is_control() => {
+                        return Err("Malformed JSON: unescaped control character".to_string())
+                    }
+                    Some(c) => value.push(c),
+                    None => return Err("Malformed JSON: unterminated string".to_string()),
+                }
+            }
+        }
+
+        fn read_escape(&mut self) -> Result<char, String> {
+            match self.chars.next() {
+                Some('"') => Ok('"'),
+                Some('\\') => Ok('\\'),
+                Some('/') => Ok('/'),
+                Some('b') => Ok('\u{8}'),
+                Some('f') => Ok('\u{c}'),
+                Some('n') => Ok('\n'),
+                Some('r') => Ok('\r'),
+                Some('t') => Ok('\t'),
+                Some('u') => {
+                    let high = self.read_hex4()?;
+
+                    let code = if (0xd800..0xdc00).contains(&high) {
+                        // a surrogate pair, the low half has to follow as another escape
+                        self.expect('\\')?;
+                        self.expect('u')?;
+                        let low = self.read_hex4()?;
+
+                        if !(0xdc00..0xe000).contains(&low) {
+                            return Err("Malformed JSON: unpaired surrogate".to_string());
+                        }
+
+                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                    } else {
+                        high
+                    };
+
+                    char::from_u32(code)
+                        .ok_or_else(|| "Malformed JSON: invalid unicode escape".to_string())
+                }
+                _ => Err("Malformed JSON: invalid escape".to_string()),
+            }
+        }
+
+        fn read_hex4(&mut self) -> Result<u32, String> {
+            (0..4).try_fold(0, |code, _| {
+                self.chars
+                    .next()
+                    .and_then(|c| c.to_digit(16))
+                    .map(|digit| code << 4 | digit)
+                    .ok_or_else(|| "Malformed JSON: invalid unicode escape".to_string())
+            })
+        }
+
+        fn read_bool(&mut self) -> Result<bool, String> {
+            self.skip_whitespace();
+            let word = self.read_word();
+
+            match word.as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(format!(
+                    "Malformed JSON: expected a boolean, found {word:?}"
+                )),
+            }
+        }
+
+        fn read_u64(&mut self) -> Result<u64, String> {
+            self.skip_whitespace();
+            let word = self.read_word();
+
+            word.parse::<u64>().map_err(|_| {
+                format!("Malformed JSON: expected an unsigned integer, found {word:?}")
+            })
+        }
+
+        fn read_word(&mut self) -> String {
+            let mut word = String::new();
+
+            while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric()) {
+                word.push(c);
+            }
+
+            word
+        }
+
+        fn read_array<T>(
+            &mut self,
+            read_item: impl Fn(&mut Self) -> Result<T, String>,
+        ) -> Result<Vec<T>, String> {
+            let mut items = Vec::new();
+            self.expect('[')?;
+
+            if !self.next_is(']') {
+                loop {
+                    items.push(read_item(self)?);
+
+                    if self.next_is(']') {
+                        break;
+                    }
+
+                    self.expect(',')?;
+                }
+            }
+
+            self.expect(']')?;
+
+            Ok(items)
+        }
+
+        fn finish(mut self) -> Result<(), String> {
+            self.skip_whitespace();
+
+            match self.chars.next() {
+                None => Ok(()),
+                Some(_) => Err("Malformed JSON: trailing data after the proof".to_string()),
+            }
+        }
+    }
+
+    // pack bits least significant first, padding the last byte with zeros
+    fn pack_bits(bits: impl Iterator<Item = bool>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bits.enumerate().for_each(|(i, bit)| {
+            if i % 8 == 0 {
+                bytes.push(0);
+            }
+
+            if bit {
+                *bytes.last_mut().expect("a byte was pushed for this bit") |= 1 << (i % 8);
+            }
+        });
+
+        bytes
+    }
+
+    // the inverse of `pack_bits`, rejecting set padding bits so that every bitmap has a single
+    // encoding
+    fn unpack_bits(bytes: &[u8], count: usize) -> Result<Vec<bool>, String> {
+        let padding = bytes
+            .last()
+            .filter(|_| !count.is_multiple_of(8))
+            .map_or(0, |last| last >> (count % 8));
+
+        if padding != 0 {
+            return Err("Serialized bitmap has padding bits set".to_string());
+        }
+
+        Ok((0..count)
+            .map(|i| bytes[i / 8] & (1 << (i % 8)) != 0)
+            .collect::<_>())
+    }
+
+    impl MerkleAggregateProof {
+        // the elements the proof claims are part of the tree, in left-to-right order
+        pub fn elements(&self) -> &[String] {
+            &self.elements
+        }
+
+        // the siblings of each level from the elements up, as the sibling left of the range's
+        // first node and the one right of its last, either absent where the range's boundary
+        // needs no sibling; a malformed proof has no levels
+        pub fn siblings_by_level(&self) -> Vec<(Option<String>, Option<String>)> {
+            aggregate_boundaries(self)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(start_sibling, end_sibling)| (start_sibling.cloned(), end_sibling.cloned()))
+                .collect::<_>()
+        }
+
+        // drop the empty placeholder siblings, which are only there to keep the siblings in
+        // start/end pairs; the directions still record where they were, so the verifier puts
+        // them back
+        pub fn minimize(mut self) -> Self {
+            if let Ok(boundaries) = aggregate_boundaries(&self) {
+                self.siblings = boundaries
+                    .into_iter()
+                    .flat_map(|(start_sibling, end_sibling)| [start_sibling, end_sibling])
+                    .flatten()
+                    .cloned()
+                    .collect::<_>();
+            }
+
+            self
+        }
+
+        // serialize the proof as a version byte, the position of its range among the leaves,
+        // its length-prefixed elements, and its siblings with their directions
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let mut data = vec![PROOF_VERSION];
+
+            write_u64(&mut data, self.start_index as u64);
+            write_u64(&mut data, self.leaf_count as u64);
+            write_u64(&mut data, self.elements.len() as u64);
+            self.elements
+                .iter()
+                .for_each(|element| write_str(&mut data, element));
+            write_siblings(&mut data, &self.siblings, &self.directions);
+
+            data
+        }
+
+        // load an aggregate proof serialized with `to_bytes`
+        pub fn from_bytes(data: &[u8]) -> Result<MerkleAggregateProof, String> {
+            MerkleAggregateProof::from_bytes_with_config(data, TreeConfig::default())
+        }
+
+        // `from_bytes` for a proof from a tree built with a non-default configuration
+        pub fn from_bytes_with_config(
+            data: &[u8],
+            config: TreeConfig,
+        ) -> Result<MerkleAggregateProof, String> {
+            let mut reader = ByteReader::new(data);
+
+            read_proof_version(&mut reader)?;
+            let start_index = reader.read_u64()? as usize;
+            let leaf_count = reader.read_u64()? as usize;
+            let element_count = reader.read_u64()?;
+            let elements = (0..element_count)
+                .map(|_| reader.read_str())
+                .collect::<Result<Vec<_>, _>>()?;
+            let (siblings, directions) = read_siblings(&mut reader)?;
+            reader.finish()?;
+
+            Ok(MerkleAggregateProof {
+                elements,
+                siblings,
+                directions,
+                start_index,
+                leaf_count,
+                config,
+            })
+        }
+    }
+
+    fn write_siblings(data: &mut Vec<u8>, siblings: &[String], directions: &[bool]) {
+        write_u64(data, siblings.len() as u64);
+        siblings.iter().for_each(|sibling| write_str(data, sibling));
+
+        write_u64(data, directions.len() as u64);
+        directions
+            .iter()
+            .for_each(|&direction| data.push(direction as u8));
+    }
+
+    fn read_proof_version(reader: &mut ByteReader) -> Result<(), String> {
+        let version = reader.read_u8()?;
+
+        if version != PROOF_VERSION {
+            return Err(format!(
+                "Serialized data has an unsupported proof version {version}, expected {PROOF_VERSION}"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn read_arity(reader: &mut ByteReader) -> Result<usize, String> {
+        check_arity(reader.read_u64()?)
+    }
+
+    // a serialized arity, which has to be at least 2 for the proof to describe a tree at all
+    fn check_arity(arity: u64) -> Result<usize, String> {
+        match usize::try_from(arity) {
+            Ok(arity) if arity >= 2 => Ok(arity),
+            _ => Err(format!("Serialized proof has an invalid arity {arity}")),
+        }
+    }
+
+    fn read_siblings(reader: &mut ByteReader) -> Result<(Vec<String>, Vec<bool>), String> {
+        let sibling_count = reader.read_u64()?;
+        let siblings = (0..sibling_count)
+            .map(|_| reader.read_str())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let direction_count = reader.read_u64()?;
+        let directions = (0..direction_count)
+            .map(|_| match reader.read_u8()? {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err("Serialized direction is not a boolean".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((siblings, directions))
+    }
+
+    // which side of a node its sibling sits on
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Side {
+        Left,
+        Right,
+    }
+
+    // structural problems with a proof, reported by the checked verifiers instead of panicking
+    // or quietly failing verification
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum MerkleError {
+        MismatchedProofLengths { siblings: usize, directions: usize },
+        UnpairedAggregateSiblings(usize),
+        EmptyAggregateProof,
+        ProofTooLong { length: usize, max: usize },
+    }
+
+    impl fmt::Display for MerkleError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                MerkleError::MismatchedProofLengths {
+                    siblings,
+                    directions,
+                } => write!(
+                    f,
+                    "proof has {siblings} siblings but {directions} directions"
+                ),
+                MerkleError::UnpairedAggregateSiblings(count) => write!(
+                    f,
+                    "aggregate proof has {count} directions, which leaves a start/end pair incomplete"
+                ),
+                MerkleError::EmptyAggregateProof => {
+                    write!(f, "aggregate proof does not cover any elements")
+                }
+                MerkleError::ProofTooLong { length, max } => write!(
+                    f,
+                    "proof too long, it has {length} siblings where at most {max} are allowed"
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for MerkleError {}
+
+    // a root hash, checked to be a 64 character lowercase hex string when built from input
+    // so that a typo'd or truncated root is caught before it reaches a verifier
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct RootHash(String);
+
+    impl RootHash {
+        pub fn as_str(&self) -> &str {
+            &self.0
+        }
+
+        // validate a root rendered in the given encoding, `try_from` being the hex case
+        pub fn try_from_encoded(value: &str, encoding: Encoding) -> Result<Self, String> {
+            match encoding {
+                Encoding::Hex => RootHash::try_from(value),
+                Encoding::Base64 => match BASE64.decode(value) {
+                    Ok(digest) if digest.len() == 32 => Ok(RootHash(value.to_string())),
+                    Ok(_) => Err("Root hash must encode a 32 byte digest".to_string()),
+                    Err(_) => Err("Root hash is not valid base64".to_string()),
+                },
+            }
+        }
+    }
+
+    impl TryFrom<&str> for RootHash {
+        type Error = String;
+
+        fn try_from(value: &str) -> Result<Self, Self::Error> {
+            if value.len() != 64 {
+                return Err(format!(
+                    "Root hash must be 64 hex characters, received {}",
+                    value.len()
+                ));
+            }
+
+            if !value
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+            {
+                return Err("Root hash must only contain lowercase hex characters".to_string());
+            }
+
+            Ok(RootHash(value.to_string()))
+        }
+    }
+
+    impl std::ops::Deref for RootHash {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl AsRef<str> for RootHash {
+        fn as_ref(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl fmt::Display for RootHash {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl PartialEq<str> for RootHash {
+        fn eq(&self, other: &str) -> bool {
+            self.0 == other
+        }
+    }
+
+    impl PartialEq<&str> for RootHash {
+        fn eq(&self, other: &&str) -> bool {
+            self.0 == *other
+        }
+    }
+
+    impl PartialEq<String> for RootHash {
+        fn eq(&self, other: &String) -> bool {
+            &self.0 == other
+        }
+    }
+
+    // return the root hash of the merkle tree
+    //
+    // the root is taken as-is rather than validated, since a custom `MerkleHasher` is free to
+    // produce digests of other lengths
+    pub fn get_root(ref_tree: &MerkleTree) -> RootHash {
+        RootHash(ref_tree.root_hash.to_owned())
+    }
+
+    // the raw 32 bytes of the root, decoded from the tree's encoding, for byte-oriented APIs
+    // such as signing or on-chain calldata
+    //
+    // fails for a root that doesn't decode to 32 bytes, which is a corrupted tree for any of
+    // the hashers here, but can also be a custom `MerkleHasher` with another digest length
+    pub fn get_root_bytes(ref_tree: &MerkleTree) -> Result<[u8; 32], String> {
+        let decoded = match ref_tree.config.encoding {
+            Encoding::Hex => hex::decode(&ref_tree.root_hash).map_err(|e| e.to_string()),
+            Encoding::Base64 => BASE64
+                .decode(&ref_tree.root_hash)
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Root of the tree does not decode: {e}"))?;
+
+        decoded.try_into().map_err(|decoded: Vec<u8>| {
+            format!("Root of the tree is {} bytes, not 32", decoded.len())
+        })
+    }
+
+    // check that the stored root of the tree is the one its leaves actually hash to
+    //
+    // trees built by hand or loaded from elsewhere can carry a root that doesn't match their
+    // leaves, so check them before trusting anything derived from `get_root`
+    pub fn validate_integrity(tree: &MerkleTree) -> bool {
+        if tree.leaves.is_empty() {
+            return false;
+        }
+
+        let levels = generate_levels(&tree.leaves, tree.weights.as_deref(), &tree.config);
+
+        levels[levels.len() - 1][0] == tree.root_hash
+    }
+
+    // create a merkle tree from a list of elements
+    // the tree should have the minimum height needed to contain all elements
+    // empty slots should be filled with an empty string
+    //
+    // a single element is padded like any other odd row, so its tree has two leaves and a root
+    // of `hash_node(hash_leaf(element), hash_leaf(""))`, with a one-sibling proof for the element
+    #[allow(clippy::ptr_arg)] // signature provided by the challenge
+    pub fn create_merkle_tree(elements: &Vec<String>) -> Result<MerkleTree, String> {
+        create_merkle_tree_with_config(elements, TreeConfig::default())
+    }
+
+    // create a merkle tree from a list of elements, hashed according to the given configuration
+    pub fn create_merkle_tree_with_config(
+        elements: &[String],
+        config: TreeConfig,
+    ) -> Result<MerkleTree, String> {
+        let element_count = elements.len();
+        let leaves = pad_leaves(elements, &config)?;
+
+        Ok(tree_from_leaves(leaves, element_count, config))
+    }
+
+    // create a perfect merkle tree, with the leaves padded up to the next power of two rather
+    // than to an even count, for implementations that only handle full binary trees
+    //
+    // this is a tree of fixed depth, just large enough for the elements; as with the default
+    // padding a single element still gets a tree of two leaves
+    pub fn create_full_merkle_tree(elements: &[String]) -> Result<MerkleTree, String> {
+        if elements.is_empty() {
+            return Err("A tree needs at least one element".to_string());
+        }
+
+        let depth = elements.len().next_power_of_two().trailing_zeros().max(1);
+
+        create_merkle_tree_with_config(
+            elements,
+            TreeConfig::default().with_fixed_depth(depth as usize),
+        )
+    }
+
+    // create a merkle tree from a list of elements it takes ownership of, moving them into the
+    // leaves of the tree rather than cloning them as `create_merkle_tree` does
+    pub fn create_merkle_tree_owned(elements: Vec<String>) -> Result<MerkleTree, String> {
+        create_merkle_tree_owned_with_config(elements, TreeConfig::default())
+    }
+
+    // `create_merkle_tree_owned` hashed according to the given configuration
+    pub fn create_merkle_tree_owned_with_config(
+        mut elements: Vec<String>,
+        config: TreeConfig,
+    ) -> Result<MerkleTree, String> {
+        let element_count = elements.len();
+        check_leaf_limits(element_count, &config)?;
+        check_depth_limit(element_count, &config)?;
+        pad_in_place(&mut elements, &config)?;
+
+        Ok(tree_from_leaves(elements, element_count, config))
+    }
+
+    // create a merkle tree of `size` leaves from a sparse map of elements by index, with every
+    // index missing from the map filled with the padding element
+    //
+    // every index below `size` counts as an element, so the gaps can be proven like the
+    // elements around them
+    pub fn create_merkle_tree_indexed(
+        elements: &HashMap<usize, String>,
+        size: usize,
+    ) -> Result<MerkleTree, String> {
+        create_merkle_tree_indexed_with_config(elements, size, TreeConfig::default())
+    }
+
+    // `create_merkle_tree_indexed` hashed according to the given configuration, whose limits
+    // are checked against `size` before the leaves are allocated
+    pub fn create_merkle_tree_indexed_with_config(
+        elements: &HashMap<usize, String>,
+        size: usize,
+        config: TreeConfig,
+    ) -> Result<MerkleTree, String> {
+        if size == 0 {
+            return Err("A tree needs at least one element".to_string());
+        }
+
+        if let Some(index) = elements.keys().find(|&&index| index >= size) {
+            return Err(format!(
+                "Index {index} is out of bounds for a tree of {size} leaves"
+            ));
+        }
+
+        check_leaf_limits(size, &config)?;
+        check_depth_limit(size, &config)?;
+
+        let mut leaves = vec![config.padding.to_owned(); size];
+        elements
+            .iter()
+            .for_each(|(&index, element)| leaves[index] = element.to_owned());
+
+        create_merkle_tree_owned_with_config(leaves, config)
+    }
+
+    fn tree_from_leaves(
+        leaves: Vec<String>,
+        element_count: usize,
+        config: TreeConfig,
+    ) -> MerkleTree {
+        let levels = generate_levels(&leaves, None, &config);
+        let root_hash = levels[levels.len() - 1][0].to_owned();
+
+        MerkleTree {
+            leaves,
+            root_hash,
+            config,
+            element_count,
+            levels,
+            weights: None,
+        }
+    }
+
+    // create a merkle tree committing to a weight alongside each element, with every leaf
+    // hashed as `hash(element || weight.to_le_bytes())`
+    //
+    // padding leaves carry a weight of 0, other than a duplicated last leaf, which carries the
+    // weight of the leaf it duplicates
+    pub fn create_weighted_merkle_tree(elements: &[(String, u64)]) -> Result<MerkleTree, String> {
+        create_weighted_merkle_tree_with_config(elements, TreeConfig::default())
+    }
+
+    // `create_weighted_merkle_tree` hashed according to the given configuration
+    pub fn create_weighted_merkle_tree_with_config(
+        elements: &[(String, u64)],
+        config: TreeConfig,
+    ) -> Result<MerkleTree, String> {
+        check_leaf_limits(elements.len(), &config)?;
+
+        let (values, mut weights): (Vec<String>, Vec<u64>) = elements.iter().cloned().unzip();
+        let leaves = pad_leaves(&values, &config)?;
+
+        let padding_weight = match (config.odd_handling, config.fixed_depth) {
+            (OddHandling::DuplicateLast, None) => weights.last().copied().unwrap_or_default(),
+            _ => 0,
+        };
+        weights.resize(leaves.len(), padding_weight);
+
+        let levels = generate_levels(&leaves, Some(&weights), &config);
+        let root_hash = levels[levels.len() - 1][0].to_owned();
+
+        Ok(MerkleTree {
+            leaves,
+            root_hash,
+            config,
+            element_count: elements.len(),
+            levels,
+            weights: Some(weights),
+        })
+    }
+
+    // proofs over several elements, and inserting elements, don't carry weights yet
+    const WEIGHTED_TREE_UNSUPPORTED: &str = "This operation is not supported for weighted trees";
+
+    fn pad_leaves(elements: &[String], config: &TreeConfig) -> Result<Vec<String>, String> {
+        check_leaf_limits(elements.len(), config)?;
+        check_depth_limit(elements.len(), config)?;
+
+        let mut leaves = elements.to_owned();
+        pad_in_place(&mut leaves, config)?;
+
+        Ok(leaves)
+    }
+
+    // a tree of fixed depth is all padding without elements, while any other tree would have
+    // no leaves at all, and no root
+    fn pad_in_place(leaves: &mut Vec<String>, config: &TreeConfig) -> Result<(), String> {
+        match config.fixed_depth {
+            Some(depth) => leaf_depth_check(leaves, depth, &config.padding),
+            None if leaves.is_empty() => Err("A tree needs at least one element".to_string()),
+            None => {
+                leaf_pairwise_check(leaves, config);
+                Ok(())
+            }
+        }
+    }
+
+    fn check_leaf_limits(element_count: usize, config: &TreeConfig) -> Result<(), String> {
+        if config
+            .max_leaves
+            .is_some_and(|max_leaves| element_count > max_leaves)
+        {
+            return Err("leaf count exceeds configured maximum".to_string());
+        }
+
+        Ok(())
+    }
+
+    // the height of a binary tree over this many elements, from `required_height` or the fixed
+    // depth, checked against the configured maximum
+    fn check_depth_limit(element_count: usize, config: &TreeConfig) -> Result<(), String> {
+        let height = config
+            .fixed_depth
+            .unwrap_or_else(|| required_height(element_count));
+
+        if config.max_depth.is_some_and(|max_depth| height > max_depth) {
+            return Err("tree depth exceeds maximum".to_string());
+        }
+
+        Ok(())
+    }
+
+    // create a merkle tree from a list of elements, rejecting any element that repeats an
+    // earlier one, since duplicates make it ambiguous which leaf a proof refers to
+    pub fn create_merkle_tree_unique(elements: &[String]) -> Result<MerkleTree, String> {
+        let mut seen = HashSet::new();
+
+        if let Some(index) = elements.iter().position(|e| !seen.insert(e)) {
+            return Err(format!("duplicate element at index {index}"));
+        }
+
+        create_merkle_tree_with_config(elements, TreeConfig::default())
+    }
+
+    // return the height `create_merkle_tree` will produce for the given number of elements
+    //
+    // this is not simply `ceil(log2(n))`: the leaves are padded to an even count, and every odd
+    // row above them has its last node paired with an empty node, so it mirrors that loop
+    pub fn required_height(element_count: usize) -> usize {
+        // the row above the padded leaves, halved from the elements so that no count overflows
+        let mut row_len = element_count.div_ceil(2);
+        let mut height = usize::from(element_count > 0);
+
+        while row_len > 1 {
+            row_len = row_len.div_ceil(2);
+            height += 1;
+        }
+
+        height
+    }
+
+    // number of digests `create_merkle_tree` computes for this many elements, one per padded
+    // leaf and one per internal node, for estimating the cost of a build without doing it
+    //
+    // `None` for a count whose digests don't fit in a `usize`, a tree that could never be built
+    pub fn hash_op_count(element_count: usize) -> Option<usize> {
+        if element_count == 0 {
+            return Some(0);
+        }
+
+        let mut row_len = element_count.checked_add(element_count % 2)?;
+        let mut count = row_len;
+
+        while row_len > 1 {
+            row_len = row_len.div_ceil(2);
+            count = count.checked_add(row_len)?;
+        }
+
+        Some(count)
+    }
+
+    fn leaf_pairwise_check(leaves: &mut Vec<String>, config: &TreeConfig) {
+        if leaves.len() % 2 == 1 {
+            let padding = match config.odd_handling {
+                OddHandling::PadEmpty => config.padding.to_owned(),
+                OddHandling::DuplicateLast => leaves[leaves.len() - 1].to_owned(),
+            };
+
+            leaves.push(padding);
+        }
+    }
+
+    fn leaf_depth_check(
+        leaves: &mut Vec<String>,
+        depth: usize,
+        padding: &str,
+    ) -> Result<(), String> {
+        let capacity = u32::try_from(depth)
+            .ok()
+            .and_then(|depth| 1usize.checked_shl(depth))
+            .filter(|&capacity| capacity >= leaves.len())
+            .ok_or_else(|| format!("Too many elements for a tree of fixed depth {depth}"))?;
+
+        leaves.resize(capacity, padding.to_string());
+
+        Ok(())
+    }
+
+    fn generate_parent(left: MerkleNode, right: MerkleNode, config: &TreeConfig) -> MerkleNode {
+        MerkleNode {
+            value: config.hash_node(&left.value, &right.value),
+            left: Some(Box::new(left)),
+            right: Some(Box::new(right)),
+        }
+    }
+
+    // the nodes are moved into their parents rather than cloned, so building a row costs one
+    // allocation per parent no matter how deep the subtrees below it are
+    fn generate_parent_row(nodes: Vec<MerkleNode>, config: &TreeConfig) -> Vec<MerkleNode> {
+        merkle_core::parent_row(nodes, |left, right| {
+            let right = right.unwrap_or_else(|| config.odd_sibling(&left));
+            generate_parent(left, right, config)
+        })
+    }
+
+    // return a merkle proof of the inclusion of element at the given index
+    //
+    // example:
+    // proof for index 2 (marked with E), return the nodes marked `*` at each layer.
+    //
+    // tree:
+    // d0:                                   [ R ]
+    // d1:                [*]                                     [*]
+    // d2:      [*]                 [*]                 [ ]                 [ ]
+    // d3: [ ]       [ ]       [E]       [*]       [ ]       [ ]       [ ]       [ ]
+    //
+    // proof:
+    // element    = E
+    // siblings   = [d3-3, d2-0, d1-1]
+    // directions = [false, true, false]
+    //
+    // padding leaves are rejected, since a proof of the padding element is almost never what
+    // the caller meant to ask for
+    pub fn get_proof(ref_tree: &MerkleTree, index: usize) -> Result<MerkleProof, String> {
+        if ref_tree.is_padding(index) {
+            return Err("requested index is a padding leaf, not a real element".to_string());
+        }
+
+        get_proof_allow_padding(ref_tree, index)
+    }
+
+    // `get_proof`, also proving the leaves appended to pad the tree
+    pub fn get_proof_allow_padding(
+        ref_tree: &MerkleTree,
+        index: usize,
+    ) -> Result<MerkleProof, String> {
+        if index >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        Ok(collect_proof(ref_tree, index, usize::MAX))
+    }
+
+    // return a merkle proof of the inclusion of element at the given index, climbing only
+    // the lowest `up_to_level` levels of the tree (level 0 being the leaves)
+    //
+    // the proof verifies against the hash of the intermediate node reached at `up_to_level`
+    // rather than the root, so it can be plugged into a higher-level commitment.
+    pub fn get_partial_proof(
+        ref_tree: &MerkleTree,
+        index: usize,
+        up_to_level: usize,
+    ) -> Result<MerkleProof, String> {
+        if index >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        let proof = collect_proof(ref_tree, index, up_to_level);
+
+        if proof.siblings.len() < up_to_level {
+            return Err("Requested level is above the root of this tree".to_string());
+        }
+
+        Ok(proof)
+    }
+
+    // return a merkle proof for every element of the tree, in leaf order, leaving out the
+    // padding leaves as `get_proof` does
+    //
+    // the levels of the tree are generated once and shared by all proofs, so this is
+    // O(n*log(n)) overall rather than the O(n^2*log(n)) of calling `get_proof` for each leaf
+    pub fn get_all_proofs(ref_tree: &MerkleTree) -> Result<Vec<MerkleProof>, String> {
+        Ok(all_paths(ref_tree))
+    }
+
+    // the path of every element's leaf up to the root, in leaf order, for rendering the whole
+    // tree; `get_all_proofs` without the `Result`, as it never fails
+    pub fn all_paths(ref_tree: &MerkleTree) -> Vec<MerkleProof> {
+        (0..ref_tree.element_count)
+            .map(|index| collect_proof(ref_tree, index, usize::MAX))
+            .collect::<_>()
+    }
+
+    // for every leaf, which of the internal nodes are on its path to the root, with the nodes
+    // numbered a level at a time from the one above the leaves up to the root, left to right,
+    // so leaves sharing a node can be coloured alike in a diagram
+    pub fn path_overlap_matrix(ref_tree: &MerkleTree) -> Vec<Vec<bool>> {
+        let internal_rows = &ref_tree.levels[1..];
+
+        (0..ref_tree.leaves.len())
+            .map(|index| {
+                internal_rows
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(level, row)| {
+                        let ancestor = index >> (level + 1);
+                        (0..row.len()).map(move |node| node == ancestor)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<_>()
+    }
+
+    // build a tree and a proof for each of its elements in one go, for handing every
+    // participant their proof straight after the build
+    //
+    // the proofs are filled in a row at a time as the levels are walked from the leaves up,
+    // O(n*log(n)) overall; padding leaves get no proof, as with `get_proof`
+    pub fn build_with_proofs(
+        elements: &[String],
+    ) -> Result<(MerkleTree, Vec<MerkleProof>), String> {
+        let tree = create_merkle_tree_with_config(elements, TreeConfig::default())?;
+
+        let mut proofs = tree.leaves[..tree.element_count]
+            .iter()
+            .map(|element| MerkleProof {
+                element: element.to_owned(),
+                siblings: Vec::with_capacity(tree.height()),
+                directions: Vec::with_capacity(tree.height()),
+                config: tree.config.to_owned(),
+                weight: None,
+                arity: 2,
+            })
+            .collect::<Vec<_>>();
+
+        for (level, row) in tree.levels[..tree.height()].iter().enumerate() {
+            for (index, proof) in proofs.iter_mut().enumerate() {
+                let (sibling, side) = sibling_in_row(row, index >> level, &tree.config);
+                proof.siblings.push(sibling);
+                proof.directions.push(side == Side::Left);
+            }
+        }
+
+        Ok((tree, proofs))
+    }
+
+    // rebuild the tree a complete set of proofs was generated from, one proof per element as
+    // `build_with_proofs` returns them, placing each element at the index its proof climbs from
+    //
+    // the proofs have to cover every index from 0 up exactly once, share a configuration, and
+    // all verify against the rebuilt root, so this doubles as a consistency check of the set
+    pub fn reconstruct_from_proofs(proofs: &[MerkleProof]) -> Result<MerkleTree, String> {
+        let first = proofs
+            .first()
+            .ok_or_else(|| "Cannot reconstruct a tree from no proofs".to_string())?;
+
+        if proofs.iter().any(|proof| proof.weight.is_some()) {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        if proofs.iter().any(|proof| proof.config != first.config) {
+            return Err("Proofs are from trees with different configurations".to_string());
+        }
+
+        let mut elements = vec![None; proofs.len()];
+
+        for proof in proofs {
+            let index = proof_index(proof)
+                .ok_or_else(|| "Proof has more levels than any tree can have".to_string())?;
+
+            match elements.get_mut(index) {
+                Some(slot @ None) => *slot = Some(proof.element.to_owned()),
+                Some(Some(_)) => return Err(format!("More than one proof is for index {index}")),
+                None => {
+                    return Err(format!(
+                        "Proof for index {index} is beyond the {} proofs given",
+                        proofs.len()
+                    ))
+                }
+            }
+        }
+
+        // as many proofs as slots, none of them sharing one, leaves every slot filled
+        let elements = elements.into_iter().flatten().collect::<Vec<_>>();
+        let tree = create_merkle_tree_with_config(&elements, first.config.to_owned())?;
+        let root = get_root(&tree);
+
+        match proofs.iter().position(|proof| !verify_proof(&root, proof)) {
+            Some(failed) => Err(format!(
+                "Proof {failed} does not verify against the reconstructed root"
+            )),
+            None => Ok(tree),
+        }
+    }
+
+    // return a merkle proof for each of the given indices, in the same order, generated across
+    // rayon's thread pool; each proof only reads the levels of the tree, so they are
+    // independent of each other
+    //
+    // fails like `get_proof` if any of the indices would be rejected by it
+    #[cfg(feature = "rayon")]
+    pub fn get_proofs_parallel(
+        ref_tree: &MerkleTree,
+        indices: &[usize],
+    ) -> Result<Vec<MerkleProof>, String> {
+        use rayon::prelude::*;
+
+        indices
+            .par_iter()
+            .map(|&index| get_proof(ref_tree, index))
+            .collect::<_>()
+    }
+
+    // upper bound on the size in bytes of any proof of the tree serialized with
+    // `MerkleProof::to_bytes`, not counting its element, which varies from one proof to the next
+    //
+    // every proof of a tree has one sibling per level and every digest the same encoded length,
+    // so adding the element's length gives the exact size, except for a proof climbing past the
+    // last node of an odd row above the leaves, whose empty sibling takes no digest bytes
+    pub fn estimated_proof_bytes(ref_tree: &MerkleTree) -> usize {
+        const LEN: usize = std::mem::size_of::<u64>();
+
+        let digest_len = ref_tree.root_hash.len();
+        let weight_len = match ref_tree.weights {
+            Some(_) => LEN,
+            None => 0,
+        };
+
+        // version, element length, weight flag, arity, and the sibling and direction counts
+        1 + LEN
+            + 1
+            + weight_len
+            + LEN
+            + LEN
+            + ref_tree.height() * (LEN + digest_len)
+            + LEN
+            + ref_tree.height()
+    }
+
+    // number of nodes in every row of a tree with this many (padded) leaves, from the leaves up
+    // to the root
+    fn row_lens(leaf_count: usize) -> Vec<usize> {
+        let mut row_lens = vec![leaf_count];
+
+        while row_lens[row_lens.len() - 1] > 1 {
+            row_lens.push(row_lens[row_lens.len() - 1].div_ceil(2));
+        }
+
+        row_lens
+    }
+
+    // hashes of every row of the tree, from the leaves (level 0) up to the root
+    fn generate_levels(
+        leaves: &[String],
+        weights: Option<&[u64]>,
+        config: &TreeConfig,
+    ) -> Vec<Vec<String>> {
+        let leaf_row: Vec<MerkleNode> = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| MerkleNode::weighted_leaf(leaf, weights.map(|w| w[i]), config))
+            .collect::<_>();
+
+        levels_from_row(leaf_row, config)
+    }
+
+    // hashes of every row from the given one up to the root
+    fn levels_from_row(mut current_row: Vec<MerkleNode>, config: &TreeConfig) -> Vec<Vec<String>> {
+        let mut levels = vec![row_hashes(&current_row)];
+
+        while current_row.len() > 1 {
+            current_row = generate_parent_row(current_row, config);
+            levels.push(row_hashes(&current_row));
+        }
+
+        levels
+    }
+
+    fn row_hashes(row: &[MerkleNode]) -> Vec<String> {
+        row.iter().map(|node| node.value.to_owned()).collect::<_>()
+    }
+
+    // return the hashes of every node at the given level, in left-to-right order, from the
+    // leaf digests at level 0 up to the root alone at the height of the tree
+    pub fn level_hashes(ref_tree: &MerkleTree, level: usize) -> Result<Vec<String>, String> {
+        ref_tree.levels.get(level).cloned().ok_or_else(|| {
+            format!(
+                "Requested level is above the root of this tree, at level {}",
+                ref_tree.height()
+            )
+        })
+    }
+
+    // return the hash of the node at the given level and position within it, the root of the
+    // subtree covering leaves `[node_index << level, (node_index + 1) << level)`
+    pub fn subtree_root(
+        ref_tree: &MerkleTree,
+        level: usize,
+        node_index: usize,
+    ) -> Result<String, String> {
+        let row = ref_tree.levels.get(level).ok_or_else(|| {
+            format!(
+                "Requested level is above the root of this tree, at level {}",
+                ref_tree.height()
+            )
+        })?;
+
+        row.get(node_index).cloned().ok_or_else(|| {
+            format!(
+                "Requested node is out of bounds for level {level}, which has {} nodes",
+                row.len()
+            )
+        })
+    }
+
+    // split a tree into the two subtrees under its root, for sharding a commitment, with
+    // `boundary` the number of leaves under the root's left child (2^(height-1))
+    //
+    // each half keeps its rows of the original tree as they are, and `combine_roots` of the two
+    // gives back the original root
+    //
+    // only trees with a power of two (padded) leaves can be split: the right half of any other
+    // tree was paired with empty nodes on its way up, rows it can't be rebuilt to on its own
+    pub fn split_at(
+        ref_tree: &MerkleTree,
+        boundary: usize,
+    ) -> Result<(MerkleTree, MerkleTree), String> {
+        if ref_tree.height() == 0 {
+            return Err("A tree of a single leaf has no subtrees to split into".to_string());
+        }
+
+        if !ref_tree.leaves.len().is_power_of_two() {
+            return Err(format!(
+                "A tree of {} leaves has no subtrees that are trees in their own right",
+                ref_tree.leaves.len()
+            ));
+        }
+
+        let root_boundary = 1 << (ref_tree.height() - 1);
+
+        if boundary != root_boundary {
+            return Err(format!(
+                "This tree can only be split under its root, at leaf {root_boundary}"
+            ));
+        }
+
+        let (left_levels, right_levels): (Vec<_>, Vec<_>) = ref_tree.levels[..ref_tree.height()]
+            .iter()
+            .enumerate()
+            .map(|(level, row)| {
+                let (left, right) = row.split_at(boundary >> level);
+                (left.to_vec(), right.to_vec())
+            })
+            .unzip();
+        let (left_leaves, right_leaves) = ref_tree.leaves.split_at(boundary);
+        let (left_weights, right_weights) = match &ref_tree.weights {
+            Some(weights) => {
+                let (left, right) = weights.split_at(boundary);
+                (Some(left.to_vec()), Some(right.to_vec()))
+            }
+            None => (None, None),
+        };
+
+        let left = MerkleTree {
+            leaves: left_leaves.to_vec(),
+            root_hash: left_levels[left_levels.len() - 1][0].to_owned(),
+            config: ref_tree.config.to_owned(),
+            element_count: ref_tree.element_count.min(boundary),
+            levels: left_levels,
+            weights: left_weights,
+        };
+        let right = MerkleTree {
+            leaves: right_leaves.to_vec(),
+            root_hash: right_levels[right_levels.len() - 1][0].to_owned(),
+            config: ref_tree.config.to_owned(),
+            element_count: ref_tree.element_count.saturating_sub(boundary),
+            levels: right_levels,
+            weights: right_weights,
+        };
+
+        Ok((left, right))
+    }
+
+    // the root of the tree with `left` and `right` as the two subtrees under its root, as
+    // split by `split_at`
+    pub fn combine_roots(left: &MerkleTree, right: &MerkleTree) -> Result<RootHash, String> {
+        if left.config != right.config {
+            return Err("Subtrees are hashed with different configurations".to_string());
+        }
+
+        Ok(RootHash(
+            left.config.hash_node(&left.root_hash, &right.root_hash),
+        ))
+    }
+
+    // return the hashes of a leaf and of each of its ancestors, from the leaf digest up to and
+    // including the root, as opposed to the siblings of the path that `get_proof` returns
+    pub fn ancestor_path(ref_tree: &MerkleTree, index: usize) -> Result<Vec<String>, String> {
+        if index >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        Ok(ref_tree
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(level, row)| row[index >> level].to_owned())
+            .collect::<_>())
+    }
+
+    // return the hash of the lowest node covering both leaves, where their paths to the root meet
+    pub fn lowest_common_ancestor(
+        ref_tree: &MerkleTree,
+        i: usize,
+        j: usize,
+    ) -> Result<String, String> {
+        if i >= ref_tree.leaves.len() || j >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        let level = (0..=ref_tree.height())
+            .find(|level| i >> level == j >> level)
+            .expect("all paths meet at the root");
+
+        Ok(ref_tree.levels[level][i >> level].to_owned())
+    }
+
+    // return the sibling of a leaf's ancestor at the given level (level 0 being the leaf itself),
+    // and which side of that ancestor it sits on
+    pub fn sibling_at_level(
+        ref_tree: &MerkleTree,
+        leaf_index: usize,
+        level: usize,
+    ) -> Result<(String, Side), String> {
+        if leaf_index >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        if level >= ref_tree.height() {
+            return Err("Requested level has no siblings in this tree".to_string());
+        }
+
+        Ok(sibling_in_row(
+            &ref_tree.levels[level],
+            leaf_index >> level,
+            &ref_tree.config,
+        ))
+    }
+
+    fn sibling_in_row(row: &[String], index: usize, config: &TreeConfig) -> (String, Side) {
+        match merkle_core::sibling_position(row.len(), index) {
+            (Some(sibling), true) => (row[sibling].to_owned(), Side::Left),
+            (Some(sibling), false) => (row[sibling].to_owned(), Side::Right),
+            // the last node of an odd row, see `generate_parent_row`
+            (None, _) => (config.odd_sibling_hash(&row[index]), Side::Right),
+        }
+    }
+
+    fn collect_proof(ref_tree: &MerkleTree, index: usize, up_to_level: usize) -> MerkleProof {
+        let element = ref_tree.leaves[index].to_owned();
+        let (siblings, directions) = (0..ref_tree.height().min(up_to_level))
+            .map(|level| {
+                let (sibling, side) =
+                    sibling_in_row(&ref_tree.levels[level], index >> level, &ref_tree.config);
+                (sibling, side == Side::Left)
+            })
+            .unzip();
+
+        MerkleProof {
+            element,
+            siblings,
+            directions,
+            config: ref_tree.config.to_owned(),
+            weight: ref_tree.weight(index),
+            arity: 2,
+        }
+    }
+
+    // a commitment binding the root of the tree to a timestamp and a nonce, as
+    // `hash(root || timestamp.to_le_bytes() || nonce)`, so that a published root can't be
+    // replayed in another context
+    pub fn commit_with_context(ref_tree: &MerkleTree, timestamp: u64, nonce: &[u8]) -> String {
+        context_commitment(&ref_tree.config, &ref_tree.root_hash, timestamp, nonce)
+    }
+
+    // verify a proof against the root, and that the root in the given context reproduces the
+    // commitment, hashed the same way as the tree the proof was generated from
+    pub fn verify_context_commitment(
+        commitment: &str,
+        root: &RootHash,
+        timestamp: u64,
+        nonce: &[u8],
+        proof: &MerkleProof,
+    ) -> bool {
+        context_commitment(&proof.config, root.as_str(), timestamp, nonce) == commitment
+            && verify_proof(root, proof)
+    }
+
+    fn context_commitment(config: &TreeConfig, root: &str, timestamp: u64, nonce: &[u8]) -> String {
+        let mut input = root.as_bytes().to_vec();
+        input.extend_from_slice(&timestamp.to_le_bytes());
+        input.extend_from_slice(nonce);
+
+        config.digest(&input)
+    }
+
+    // verify a merkle sub-tree against a known root
+    pub fn verify_proof(root: &RootHash, proof: &MerkleProof) -> bool {
+        proof_root(proof).eq(root.as_str())
+    }
+
+    // whether the root a proof reconstructs to starts with the given prefix, for filtering
+    // proofs against a root truncated in a log line before verifying them properly
+    //
+    // this is not verification: a short prefix can be matched by brute force, so a proof
+    // passing it proves nothing on its own; an empty prefix matches nothing
+    pub fn verify_proof_root_prefix(root_prefix: &str, proof: &MerkleProof) -> bool {
+        !root_prefix.is_empty() && proof_root(proof).starts_with(root_prefix)
+    }
+
+    // verify a proof as-is, or failing that with every direction flipped, for proofs from
+    // systems that disagree on whether `true` means the sibling is on the left or the right;
+    // only binary proofs are retried flipped
+    //
+    // this is a diagnostic convenience for working out which convention a proof follows, not
+    // something to verify with in production: it accepts proofs no single convention would
+    pub fn verify_proof_either_direction(root: &RootHash, proof: &MerkleProof) -> bool {
+        if verify_proof(root, proof) {
+            return true;
+        }
+
+        // a k-ary proof's directions say where the path's node sits among its siblings, which
+        // has no single flipped reading
+        if proof.arity != 2 {
+            return false;
+        }
+
+        let flipped_directions = proof.directions.iter().map(|d| !d).collect::<Vec<_>>();
+
+        fold_siblings(
+            &proof.config,
+            proof
+                .config
+                .hash_weighted_leaf(&proof.element, proof.weight),
+            &proof.siblings,
+            &flipped_directions,
+        )
+        .eq(root.as_str())
+    }
+
+    // verify an ed25519 signature over the root, as its raw digest bytes, and then the proof
+    // against that root, both of which have to pass
+    //
+    // a malformed public key or signature is an error, while a well-formed signature that
+    // doesn't match is a failed verification like any other
+    #[cfg(feature = "ed25519")]
+    pub fn verify_signed_proof(
+        root: &RootHash,
+        signature: &[u8],
+        public_key: &[u8],
+        proof: &MerkleProof,
+    ) -> Result<bool, String> {
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+        let public_key = public_key
+            .try_into()
+            .map_err(|_| "An ed25519 public key has to be 32 bytes".to_string())?;
+        let verifying_key = VerifyingKey::from_bytes(public_key)
+            .map_err(|e| format!("Invalid ed25519 public key: {e}"))?;
+        let signature = Signature::from_slice(signature)
+            .map_err(|e| format!("Invalid ed25519 signature: {e}"))?;
+
+        let root_bytes = proof.config.decode_digest(root.as_str());
+
+        Ok(verifying_key.verify(&root_bytes, &signature).is_ok() && verify_proof(root, proof))
+    }
+
+    // verify a merkle proof against a root held as raw digest bytes, comparing in byte space
+    // rather than encoding the root for the comparison
+    pub fn verify_proof_bytes(root: &[u8; 32], proof: &MerkleProof) -> bool {
+        proof.config.decode_digest(&proof_root(proof)) == root
+    }
+
+    // verify a merkle proof against a root as Ethereum tooling renders a `bytes32`, with an
+    // optional `0x` prefix and hex digits of either case
+    //
+    // fails for anything that isn't 64 hex digits once the prefix is stripped, as `RootHash`
+    // would, rather than treating it as a root that doesn't match
+    pub fn verify_proof_0x(root: &str, proof: &MerkleProof) -> Result<bool, String> {
+        let unprefixed = root
+            .strip_prefix("0x")
+            .or_else(|| root.strip_prefix("0X"))
+            .unwrap_or(root);
+        let root = RootHash::try_from(unprefixed.to_ascii_lowercase().as_str())?;
+
+        Ok(verify_proof(&root, proof))
+    }
+
+    // the index of the leaf a proof starts from, read off its directions from the bottom up:
+    // the siblings on the left of the path's node at a level are its position among its
+    // parent's children, a digit of the index in base arity (a bit, for a binary proof)
+    //
+    // `None` for a proof with more levels than a `usize` index can address
+    pub fn proof_index(proof: &MerkleProof) -> Option<usize> {
+        let group_len = proof.arity.saturating_sub(1).max(1);
+        let mut place = Some(1usize);
+
+        proof
+            .directions
+            .chunks(group_len)
+            .try_fold(0usize, |index, directions| {
+                let position = directions.iter().filter(|&&is_left| is_left).count();
+                let level_place = place?;
+                place = level_place.checked_mul(proof.arity);
+
+                level_place.checked_mul(position)?.checked_add(index)
+            })
+    }
+
+    // verify a merkle proof against a known root, rejecting it outright if it doesn't have
+    // exactly one sibling per level of a tree of the expected height (see `required_height`)
+    pub fn verify_proof_with_height(
+        root: &RootHash,
+        proof: &MerkleProof,
+        expected_height: usize,
+    ) -> bool {
+        proof.siblings.len() == expected_height
+            && proof.directions.len() == expected_height
+            && verify_proof(root, proof)
+    }
+
+    // return the root hash a proof reconstructs to, without comparing it against anything
+    //
+    // proofs of any arity are reconstructed, a malformed k-ary proof to an empty root that no
+    // `RootHash` is equal to
+    pub fn proof_root(proof: &MerkleProof) -> String {
+        let config = &proof.config;
+        let leaf_hash = config.hash_weighted_leaf(&proof.element, proof.weight);
+
+        if proof.arity == 2 {
+            return fold_siblings(config, leaf_hash, &proof.siblings, &proof.directions);
+        }
+
+        fold_kary_siblings(proof, leaf_hash).unwrap_or_default()
+    }
+
+    // `fold_siblings` for a proof with arity-1 siblings per level, the ones on the left of the
+    // path's node coming first
+    fn fold_kary_siblings(proof: &MerkleProof, leaf_hash: String) -> Option<String> {
+        let group_len = proof.arity.checked_sub(1).filter(|&len| len > 0)?;
+
+        if proof.siblings.len() != proof.directions.len()
+            || !proof.siblings.len().is_multiple_of(group_len)
+        {
+            return None;
+        }
+
+        proof
+            .siblings
+            .chunks(group_len)
+            .zip(proof.directions.chunks(group_len))
+            .try_fold(leaf_hash, |current_hash, (siblings, directions)| {
+                let position = directions.iter().take_while(|&&is_left| is_left).count();
+
+                if directions[position..].iter().any(|&is_left| is_left) {
+                    return None;
+                }
+
+                let mut children = siblings.iter().map(String::as_str).collect::<Vec<_>>();
+                children.insert(position, &current_hash);
+
+                Some(proof.config.hash_children(&children))
+            })
+    }
+
+    // verify a merkle proof against a known root, also returning every hash computed on the way,
+    // from the leaf digest up to the root the proof reconstructs, for auditing against a tree
+    // built independently
+    //
+    // only binary proofs can be traced, a k-ary one fails with an empty trace
+    pub fn verify_proof_trace(root: &RootHash, proof: &MerkleProof) -> (bool, Vec<String>) {
+        if proof.arity != 2 {
+            return (false, Vec::new());
+        }
+
+        let config = &proof.config;
+        let mut trace = vec![config.hash_weighted_leaf(&proof.element, proof.weight)];
+
+        proof
+            .siblings
+            .iter()
+            .zip(proof.directions.iter())
+            .for_each(|(sibling, is_left_child)| {
+                let current_hash = &trace[trace.len() - 1];
+                let parent = if *is_left_child {
+                    config.hash_node(sibling, current_hash)
+                } else {
+                    config.hash_node(current_hash, sibling)
+                };
+
+                trace.push(parent);
+            });
+
+        (trace[trace.len() - 1] == root.as_str(), trace)
+    }
+
+    // verify a binary proof against a known root, also checking the node it climbs through at
+    // each of the given levels (level 0 being the leaf digest) against a hash a trusted party
+    // published for it, stopping at the first mismatch rather than hashing the rest of the way
+    //
+    // a checkpoint above the top of the proof can never be checked, so it fails the proof
+    pub fn verify_proof_with_expected_intermediate(
+        root: &RootHash,
+        proof: &MerkleProof,
+        checkpoints: &[(usize, String)],
+    ) -> bool {
+        let height = proof.siblings.len();
+
+        if proof.arity != 2
+            || height != proof.directions.len()
+            || checkpoints.iter().any(|(level, _)| *level > height)
+        {
+            return false;
+        }
+
+        let config = &proof.config;
+        let matches_checkpoints = |level: usize, hash: &str| {
+            checkpoints
+                .iter()
+                .filter(|(checkpoint_level, _)| *checkpoint_level == level)
+                .all(|(_, expected)| expected == hash)
+        };
+
+        let leaf_hash = config.hash_weighted_leaf(&proof.element, proof.weight);
+
+        if !matches_checkpoints(0, &leaf_hash) {
+            return false;
+        }
+
+        proof
+            .siblings
+            .iter()
+            .zip(proof.directions.iter())
+            .enumerate()
+            .try_fold(
+                leaf_hash,
+                |current_hash, (level, (sibling, is_left_child))| {
+                    let parent = if *is_left_child {
+                        config.hash_node(sibling, &current_hash)
+                    } else {
+                        config.hash_node(&current_hash, sibling)
+                    };
+
+                    matches_checkpoints(level + 1, &parent).then_some(parent)
+                },
+            )
+            .is_some_and(|computed| root.eq(&computed))
+    }
+
+    // verify a path of siblings against a known root, starting from the digest of the leaf
+    // rather than its plaintext, for callers that are only ever handed the digest
+    //
+    // the path is hashed with the default configuration, as `hash_node` does
+    pub fn verify_proof_prehashed(
+        root: &RootHash,
+        leaf_hash: &str,
+        siblings: &[String],
+        directions: &[bool],
+    ) -> bool {
+        siblings.len() == directions.len()
+            && fold_siblings(
+                &TreeConfig::default(),
+                leaf_hash.to_string(),
+                siblings,
+                directions,
+            )
+            .eq(root.as_str())
+    }
+
+    fn fold_siblings(
+        config: &TreeConfig,
+        leaf_hash: String,
+        siblings: &[String],
+        directions: &[bool],
+    ) -> String {
+        merkle_core::fold_path(
+            leaf_hash,
+            siblings.iter().zip(directions.iter().copied()),
+            |current_hash, sibling, is_left_child| {
+                Some(match is_left_child {
+                    true => config.hash_node(sibling, &current_hash),
+                    false => config.hash_node(&current_hash, sibling),
+                })
+            },
+        )
+        .expect("every sibling of a string path can be hashed")
+    }
+
+    // longest proof `verify_proof_checked` accepts, one sibling per level of a tree of 2^64 leaves
+    pub const DEFAULT_MAX_PROOF_LEN: usize = 64;
+
+    // verify a merkle proof against a known root, first checking that it is well-formed and
+    // no longer than `DEFAULT_MAX_PROOF_LEN`, so adversarial input is rejected before any hashing
+    //
+    // siblings equal to the hash they are paired with aren't rejected up front: duplicate
+    // elements and `OddHandling::DuplicateLast` produce them in valid proofs, and a forged proof
+    // collapsing levels onto its own hash can't reach the root without a hash collision
+    pub fn verify_proof_checked(root: &RootHash, proof: &MerkleProof) -> Result<bool, MerkleError> {
+        verify_proof_checked_with_max_len(root, proof, DEFAULT_MAX_PROOF_LEN)
+    }
+
+    // `verify_proof_checked` with a custom cap on the number of siblings
+    pub fn verify_proof_checked_with_max_len(
+        root: &RootHash,
+        proof: &MerkleProof,
+        max_proof_len: usize,
+    ) -> Result<bool, MerkleError> {
+        if proof.siblings.len() > max_proof_len {
+            return Err(MerkleError::ProofTooLong {
+                length: proof.siblings.len(),
+                max: max_proof_len,
+            });
+        }
+
+        if proof.siblings.len() != proof.directions.len() {
+            return Err(MerkleError::MismatchedProofLengths {
+                siblings: proof.siblings.len(),
+                directions: proof.directions.len(),
+            });
+        }
+
+        Ok(verify_proof(root, proof))
+    }
+
+    // check that each claimed element is at its claimed index of the tree with the given root,
+    // stopping at the first claim that isn't
+    //
+    // each claim is verified with the path of its index, carrying the claimed element rather
+    // than the tree's, so it is checked against the root rather than against the tree's leaves;
+    // a malformed root or a claim of an index the tree can't prove is an error
+    pub fn verify_membership_set(
+        root: &str,
+        tree: &MerkleTree,
+        claims: &[(usize, String)],
+    ) -> Result<bool, String> {
+        let root = RootHash::try_from(root)?;
+
+        for (index, element) in claims {
+            let mut proof = get_proof(tree, *index)?;
+            proof.element = element.to_owned();
+
+            if !verify_proof(&root, &proof) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    // verify a batch of proofs against the same root, stopping at the first one that fails and
+    // returning its position in the batch
+    pub fn verify_batch(root: &RootHash, proofs: &[MerkleProof]) -> Result<(), usize> {
+        match proofs.iter().position(|proof| !verify_proof(root, proof)) {
+            Some(failed) => Err(failed),
+            None => Ok(()),
+        }
+    }
+
+    // whether two proofs could belong to the same tree, i.e. both reconstruct to the same root
+    pub fn same_tree(a: &MerkleProof, b: &MerkleProof) -> bool {
+        proof_root(a) == proof_root(b)
+    }
+
+    // verify a partial proof against the hash of the intermediate node it climbs to
+    pub fn verify_partial_proof(intermediate_root: String, proof: &MerkleProof) -> bool {
+        proof_root(proof).eq(&intermediate_root)
+    }
+
+    // ** BONUS (optional - easy) **
+    // Updates the Merkle tree (from leaf to root) to include the new element at index.
+    // For simplicity, the index must be within the bounds of the original vector size.
+    // If it is not, return an error.
+    pub fn update_element(
+        tree: MerkleTree,
+        index: usize,
+        element: &str,
+    ) -> Result<MerkleTree, String> {
+        check_update(&tree, index)?;
+
+        let elements = insert_element(tree.leaves, tree.element_count, index, element)?;
+
+        create_merkle_tree_with_config(&elements, tree.config)
+    }
+
+    // the root `update_element` would give the tree, leaving the tree itself untouched
+    //
+    // the element is inserted rather than replacing the one at `index`, so everything after it
+    // shifts and the tree is rebuilt rather than rehashed along a single path
+    pub fn preview_update_root(
+        tree: &MerkleTree,
+        index: usize,
+        element: &str,
+    ) -> Result<String, String> {
+        check_update(tree, index)?;
+
+        let elements = insert_element(
+            tree.leaves[..tree.element_count].to_vec(),
+            tree.element_count,
+            index,
+            element,
+        )?;
+
+        create_merkle_tree_with_config(&elements, tree.config.to_owned())
+            .map(|updated| updated.root_hash)
+    }
+
+    fn check_update(tree: &MerkleTree, index: usize) -> Result<(), String> {
+        if index >= tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        if tree.weights.is_some() {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        Ok(())
+    }
+
+    // the elements of a tree with `element` inserted at index, given its leaves to reuse
+    fn insert_element(
+        mut elements: Vec<String>,
+        element_count: usize,
+        index: usize,
+        element: &str,
+    ) -> Result<Vec<String>, String> {
+        // the leaves past the element count are the padding, any other leaf equal to the
+        // padding element is an element like the rest
+        elements.truncate(element_count);
+
+        // the bounds check is against the padded leaves, re-check against the elements alone
+        if index > element_count {
+            return Err(
+                "Index of the target element is out of bounds for the unpadded elements of this tree"
+                    .to_string(),
+            );
+        }
+
+        elements.insert(index, element.to_string());
+
+        Ok(elements)
+    }
+
+    // replace the element at index in place, rehashing only the nodes on its path to the root
+    //
+    // unlike `update_element`, which inserts the element and rebuilds the whole tree, this is
+    // O(log(n)) and yields the same root as rebuilding the tree with the element replaced
+    pub fn update_element_fast(
+        tree: &mut MerkleTree,
+        index: usize,
+        element: &str,
+    ) -> Result<(), String> {
+        if index >= tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        if tree.is_padding(index) {
+            tree.element_count = index + 1;
+        }
+
+        // the leaf keeps its weight in a weighted tree, only the element is replaced
+        tree.leaves[index] = element.to_string();
+        tree.levels[0][index] = tree.config.hash_weighted_leaf(element, tree.weight(index));
+
+        // a duplicated last leaf has to follow the element it duplicates
+        if tree.config.odd_handling == OddHandling::DuplicateLast
+            && tree.config.fixed_depth.is_none()
+            && index + 1 == tree.element_count
+            && index + 1 < tree.leaves.len()
+        {
+            tree.leaves[index + 1] = element.to_string();
+            tree.levels[0][index + 1] = tree.levels[0][index].to_owned();
+
+            if let Some(weights) = tree.weights.as_mut() {
+                weights[index + 1] = weights[index];
+            }
+        }
+
+        let mut current_index = index;
+
+        for level in 0..tree.levels.len() - 1 {
+            let left_index = current_index - current_index % 2;
+            let row = &tree.levels[level];
+            let right = match row.get(left_index + 1) {
+                Some(right) => right.to_owned(),
+                None => tree.config.odd_sibling_hash(&row[left_index]),
+            };
+            let parent = tree.config.hash_node(&row[left_index], &right);
+
+            current_index /= 2;
+            tree.levels[level + 1][current_index] = parent;
+        }
+
+        tree.root_hash = tree.levels[tree.levels.len() - 1][0].to_owned();
+
+        Ok(())
+    }
+
+    // shrink the tree down to its first n real elements, e.g. for a commitment to a prefix of
+    // the data, yielding the same tree as building one from those elements directly
+    pub fn truncate(tree: MerkleTree, n: usize) -> Result<MerkleTree, String> {
+        if n == 0 || n > tree.element_count {
+            return Err(format!(
+                "Cannot truncate a tree of {} elements to {n} elements",
+                tree.element_count
+            ));
+        }
+
+        match &tree.weights {
+            Some(weights) => create_weighted_merkle_tree_with_config(
+                &tree.leaves[..n]
+                    .iter()
+                    .cloned()
+                    .zip(weights[..n].iter().copied())
+                    .collect::<Vec<_>>(),
+                tree.config,
+            ),
+            None => create_merkle_tree_with_config(&tree.leaves[..n], tree.config),
+        }
+    }
+
+    // extend the elements of a tree with more elements, padding the combined list once, which
+    // gives the same tree as building it over the combined list from scratch
+    pub fn append_elements(tree: MerkleTree, elements: &[String]) -> Result<MerkleTree, String> {
+        if tree.weights.is_some() {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        let mut combined = tree.leaves;
+        combined.truncate(tree.element_count);
+        combined.extend_from_slice(elements);
+
+        create_merkle_tree_with_config(&combined, tree.config)
+    }
+
+    // rebuild a tree over the same elements (and weights) with a different hash function,
+    // keeping the rest of its configuration, e.g. to migrate a commitment to a new hash
+    pub fn rehash_with<H: MerkleHasher + 'static>(
+        tree: &MerkleTree,
+        hasher: H,
+    ) -> Result<MerkleTree, String> {
+        let elements = &tree.leaves[..tree.element_count];
+        let config = tree.config.to_owned().with_hasher(hasher);
+
+        match &tree.weights {
+            Some(weights) => create_weighted_merkle_tree_with_config(
+                &elements
+                    .iter()
+                    .cloned()
+                    .zip(weights.iter().copied())
+                    .collect::<Vec<_>>(),
+                config,
+            ),
+            None => create_merkle_tree_with_config(elements, config),
+        }
+    }
+
+    // return the indices of the elements satisfying the predicate, in ascending order, e.g. to
+    // build a sparse proof over them; padding leaves are not elements and are never matched
+    pub fn find_indices_where<F: Fn(&str) -> bool>(ref_tree: &MerkleTree, pred: F) -> Vec<usize> {
+        ref_tree.leaves[..ref_tree.element_count]
+            .iter()
+            .enumerate()
+            .filter(|(_, leaf)| pred(leaf))
+            .map(|(index, _)| index)
+            .collect::<_>()
+    }
+
+    // return the indices of the leaves that differ between two versions of a tree, in their
+    // element or weight, including any that are only present in the larger of the two
+    pub fn diff(old: &MerkleTree, new: &MerkleTree) -> Vec<usize> {
+        let longest = old.leaves.len().max(new.leaves.len());
+
+        (0..longest)
+            .filter(|&i| old.leaves.get(i) != new.leaves.get(i) || old.weight(i) != new.weight(i))
+            .collect::<_>()
+    }
+
+    // the `(level, index)` of the first node that differs between two trees, comparing their
+    // rows from the leaves up and each row left to right, or `None` if every node matches
+    //
+    // a node present in only one of the trees (as in a longer row or a taller tree) differs
+    pub fn first_divergent_node(a: &MerkleTree, b: &MerkleTree) -> Option<(usize, usize)> {
+        let levels = a.levels.len().max(b.levels.len());
+
+        (0..levels).find_map(|level| {
+            let (row_a, row_b) = (a.levels.get(level), b.levels.get(level));
+            let longest = row_a.map_or(0, Vec::len).max(row_b.map_or(0, Vec::len));
+
+            (0..longest)
+                .find(|&i| row_a.and_then(|row| row.get(i)) != row_b.and_then(|row| row.get(i)))
+                .map(|index| (level, index))
+        })
+    }
+
+    // ** BONUS (optional - hard) **
+    // Generates a Merkle proof of the inclusion of contiguous elements,
+    // starting at startIndex (inclusive) and ending at endIndex (exclusive).
+    // If the indexes are out of bounds or startIndex >= endIndex, an error is returned.
+    //
+    // Note: modify the method signature to return your proof type.
+    // Implement a separate verify_aggregate_proof for this type.
+    //
+    // The aggregated proof size should generally be smaller than
+    // that of the naive approach (calling GetProof for every index).
+    pub fn get_aggregate_proof(
+        ref_tree: &MerkleTree,
+        start_index: usize,
+        end_index: usize,
+    ) -> Result<MerkleAggregateProof, String> {
+        if ref_tree.weights.is_some() {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        if start_index >= end_index || end_index > ref_tree.leaves.len() {
+            return Err(
+                "Invalid range indices for the target elements.\
+                 Ensure your start and end both fall within the leaves vector for the given tree."
+                    .to_string(),
+            );
+        }
+
+        let row_lens = ref_tree.levels.iter().map(Vec::len).collect::<Vec<_>>();
+        let (siblings, directions) = aggregate_siblings(
+            &row_lens,
+            start_index,
+            end_index,
+            &ref_tree.config,
+            |level, index| ref_tree.levels[level].get(index).cloned(),
+        )?;
+
+        Ok(MerkleAggregateProof {
+            elements: ref_tree.leaves[start_index..end_index].to_vec(),
+            siblings,
+            directions,
+            start_index,
+            leaf_count: ref_tree.leaves.len(),
+            config: ref_tree.config.to_owned(),
+        })
+    }
+
+    // the siblings, and their directions, of an aggregate proof over `[start_index, end_index)`
+    // of a tree with rows of the given lengths, looking up each node with `node`
+    fn aggregate_siblings(
+        row_lens: &[usize],
+        start_index: usize,
+        end_index: usize,
+        config: &TreeConfig,
+        node: impl Fn(usize, usize) -> Option<String>,
+    ) -> Result<(Vec<String>, Vec<bool>), String> {
+        let node = |level: usize, index: usize| {
+            node(level, index).ok_or_else(|| {
+                format!("Missing the node at index {index} of level {level} needed for the proof")
+            })
+        };
+
+        let mut siblings: Vec<String> = Vec::new();
+        let mut directions: Vec<bool> = Vec::new();
+
+        let mut current_start = start_index;
+        let mut current_end = end_index - 1;
+
+        // climb until the range spans its whole row, from there the verifier can hash up to the
+        // root without any more siblings
+        for (level, &row_len) in row_lens.iter().enumerate() {
+            if current_start == 0 && current_end == row_len - 1 {
+                break;
+            }
+
+            // once both boundaries have reached the same node, only its own path to the root is
+            // left, so a level needs one sibling rather than a start/end pair
+            if current_start == current_end {
+                if current_start % 2 == 1 {
+                    siblings.push(node(level, current_start - 1)?);
+                    directions.push(true);
+                } else if current_start + 1 < row_len {
+                    siblings.push(node(level, current_start + 1)?);
+                    directions.push(false);
+                } else {
+                    // the last node of an odd row, see `generate_parent_row`
+                    siblings.push(config.odd_sibling_hash(&node(level, current_start)?));
+                    directions.push(false);
+                }
+
+                current_start /= 2;
+                current_end /= 2;
+                continue;
+            }
+
+            let start_sibling_is_left_child = current_start % 2 == 1;
+            // the last node of an odd row has no sibling in the row, the verifier pairs it the
+            // same way the tree did in `generate_parent_row`
+            let end_sibling_is_right_child =
+                current_end.is_multiple_of(2) && current_end + 1 < row_len;
+
+            if start_sibling_is_left_child {
+                siblings.push(node(level, current_start - 1)?);
+            } else {
+                siblings.push(MerkleNode::default().value.to_owned())
+            }
+
+            directions.push(start_sibling_is_left_child);
+
+            if end_sibling_is_right_child {
+                siblings.push(node(level, current_end + 1)?);
+            } else {
+                siblings.push(MerkleNode::default().value.to_owned())
+            }
+
+            directions.push(end_sibling_is_right_child);
+
+            current_start /= 2;
+            current_end /= 2;
+        }
+
+        Ok((siblings, directions))
+    }
+
+    // combine the aggregate proofs of two adjacent ranges of the same tree into a proof for the
+    // range covering both, without going back to the tree
+    //
+    // the nodes either proof reveals, or lets the verifier hash, are pooled together and the
+    // siblings of the combined range are looked up among them; the result still has to be
+    // verified, since the two proofs are not checked against each other
+    pub fn concat_aggregate_proofs(
+        left: &MerkleAggregateProof,
+        right: &MerkleAggregateProof,
+    ) -> Result<MerkleAggregateProof, String> {
+        if left.config != right.config || left.leaf_count != right.leaf_count {
+            return Err("Aggregate proofs are not from the same tree".to_string());
+        }
+
+        if left.start_index + left.elements.len() != right.start_index {
+            return Err(format!(
+                "Aggregate proofs cover the ranges [{}, {}) and [{}, {}), which are not adjacent",
+                left.start_index,
+                left.start_index + left.elements.len(),
+                right.start_index,
+                right.start_index + right.elements.len()
+            ));
+        }
+
+        let row_lens = row_lens(left.leaf_count);
+        let mut nodes = HashMap::new();
+        aggregate_known_nodes(left, &row_lens, &mut nodes)?;
+        aggregate_known_nodes(right, &row_lens, &mut nodes)?;
+
+        // hash every parent whose children are now both known
+        for level in 0..row_lens.len() - 1 {
+            let left_children = nodes
+                .keys()
+                .filter(|&&(l, index)| l == level && index % 2 == 0)
+                .map(|&(_, index)| index)
+                .collect::<Vec<_>>();
+
+            for index in left_children {
+                let left_child = &nodes[&(level, index)];
+                let right_child = if index + 1 < row_lens[level] {
+                    match nodes.get(&(level, index + 1)) {
+                        Some(right_child) => right_child.to_owned(),
+                        None => continue,
+                    }
+                } else {
+                    left.config.odd_sibling_hash(left_child)
+                };
+
+                let parent = left.config.hash_node(left_child, &right_child);
+                nodes.entry((level + 1, index / 2)).or_insert(parent);
+            }
+        }
+
+        let end_index = right.start_index + right.elements.len();
+        let (siblings, directions) = aggregate_siblings(
+            &row_lens,
+            left.start_index,
+            end_index,
+            &left.config,
+            |level, index| nodes.get(&(level, index)).cloned(),
+        )?;
+
+        Ok(MerkleAggregateProof {
+            elements: [left.elements.as_slice(), right.elements.as_slice()].concat(),
+            siblings,
+            directions,
+            start_index: left.start_index,
+            leaf_count: left.leaf_count,
+            config: left.config.to_owned(),
+        })
+    }
+
+    // record the leaf digests of an aggregate proof's elements, and its siblings, by level and
+    // index among the nodes of its tree
+    fn aggregate_known_nodes(
+        proof: &MerkleAggregateProof,
+        row_lens: &[usize],
+        nodes: &mut HashMap<(usize, usize), String>,
+    ) -> Result<(), String> {
+        let boundaries = aggregate_boundaries(proof).map_err(|e| e.to_string())?;
+
+        if proof.start_index + proof.elements.len() > row_lens[0]
+            || boundaries.len() > row_lens.len()
+        {
+            return Err("Aggregate proof does not fit in a tree of its leaf count".to_string());
+        }
+
+        proof.elements.iter().enumerate().for_each(|(i, element)| {
+            nodes.insert((0, proof.start_index + i), proof.config.hash_leaf(element));
+        });
+
+        let mut current_start = proof.start_index;
+        let mut current_end = proof.start_index + proof.elements.len() - 1;
+
+        for (level, (start_sibling, end_sibling)) in boundaries.into_iter().enumerate() {
+            if let (Some(sibling), Some(index)) = (start_sibling, current_start.checked_sub(1)) {
+                nodes.insert((level, index), sibling.to_owned());
+            }
+
+            // a right sibling past the end of the row is the pairing of an odd row's last node
+            // rather than a node of the tree
+            if let Some(sibling) = end_sibling.filter(|_| current_end + 1 < row_lens[level]) {
+                nodes.insert((level, current_end + 1), sibling.to_owned());
+            }
+
+            current_start /= 2;
+            current_end /= 2;
+        }
+
+        Ok(())
+    }
+
+    // an aggregate proof over every element of the tree, whose only siblings are the padding
+    // past the elements, since the verifier can rebuild the rest of the tree from the elements
+    //
+    // fails only for weighted trees, which aggregate proofs don't support
+    pub fn get_full_proof(ref_tree: &MerkleTree) -> Result<MerkleAggregateProof, String> {
+        get_aggregate_proof(ref_tree, 0, ref_tree.element_count)
+    }
+
+    pub fn verify_aggregate_proof(root: &RootHash, proof: &MerkleAggregateProof) -> bool {
+        let Ok(boundaries) = aggregate_boundaries(proof) else {
+            return false;
+        };
+
+        let mut current_row = proof
+            .elements
+            .iter()
+            .map(|leaf| MerkleNode::leaf(leaf, &proof.config))
+            .collect::<Vec<_>>();
+
+        for (start_sibling, end_sibling) in boundaries {
+            if let Some(start_sibling) = start_sibling {
+                current_row.insert(
+                    0,
+                    MerkleNode {
+                        value: start_sibling.to_owned(),
+                        left: None,
+                        right: None,
+                    },
+                );
+            }
+
+            if let Some(end_sibling) = end_sibling {
+                current_row.push(MerkleNode {
+                    value: end_sibling.to_owned(),
+                    left: None,
+                    right: None,
+                });
+            }
+
+            current_row = generate_parent_row(current_row, &proof.config);
+        }
+
+        while current_row.len() > 1 {
+            current_row = generate_parent_row(current_row, &proof.config);
+        }
+
+        current_row[0].value.eq(root.as_str())
+    }
+
+    // `verify_aggregate_proof` without materializing the rows of the range
+    //
+    // the elements are folded in left to right on a stack holding at most a couple of nodes
+    // per level, merging each node as soon as its sibling is known, so only O(log(n)) hashes
+    // are held at once regardless of how wide the range is
+    pub fn verify_aggregate_proof_streaming(root: &RootHash, proof: &MerkleAggregateProof) -> bool {
+        let config = &proof.config;
+
+        let Ok(boundaries) = aggregate_boundaries(proof) else {
+            return false;
+        };
+
+        if boundaries.len() >= usize::BITS as usize {
+            return false;
+        }
+
+        // the range starts on a right child exactly at the levels with a left boundary sibling
+        let start = boundaries
+            .iter()
+            .enumerate()
+            .filter(|(_, (left, _))| left.is_some())
+            .fold(0, |index, (level, _)| index | (1 << level));
+        let end = start + proof.elements.len() - 1;
+
+        let mut stack: Vec<(usize, usize, String)> = Vec::new();
+
+        for (offset, element) in proof.elements.iter().enumerate() {
+            stack.push((0, start + offset, config.hash_leaf(element)));
+
+            loop {
+                let (level, index, hash) = stack.last().expect("a node was just pushed");
+                let (level, index) = (*level, *index);
+                let boundary = boundaries.get(level);
+
+                let parent = if index % 2 == 1 {
+                    if index == start >> level {
+                        let Some((Some(left), _)) = boundary else {
+                            return false;
+                        };
+                        let parent = config.hash_node(left, hash);
+                        stack.pop();
+                        parent
+                    } else {
+                        // the left sibling is inside the range, so it was completed just before
+                        let (_, _, right) = stack.pop().expect("a node was just pushed");
+                        match stack.pop() {
+                            Some((left_level, left_index, left))
+                                if left_level == level && left_index + 1 == index =>
+                            {
+                                config.hash_node(&left, &right)
+                            }
+                            _ => return false,
+                        }
+                    }
+                } else if index == end >> level {
+                    let parent = match boundary {
+                        Some((_, Some(right))) => config.hash_node(hash, right),
+                        None if index == 0 => break,
+                        _ => config.hash_node(hash, &config.odd_sibling_hash(hash)),
+                    };
+                    stack.pop();
+                    parent
+                } else {
+                    // wait for the right sibling to be completed
+                    break;
+                };
+
+                stack.push((level + 1, index / 2, parent));
+            }
+        }
+
+        match stack.as_slice() {
+            [(level, 0, hash)] => end >> level == 0 && hash == root.as_str(),
+            _ => false,
+        }
+    }
+
+    type Boundary<'a> = (Option<&'a String>, Option<&'a String>);
+
+    // the (start, end) boundary siblings of each level of an aggregate proof, with the
+    // placeholders for unused siblings, whether or not a minimized proof left them out, and the
+    // missing side of the levels above the point the boundaries converge both put back as absent
+    fn aggregate_boundaries(
+        proof: &MerkleAggregateProof,
+    ) -> Result<Vec<Boundary<'_>>, MerkleError> {
+        let mismatched = MerkleError::MismatchedProofLengths {
+            siblings: proof.siblings.len(),
+            directions: proof.directions.len(),
+        };
+
+        if proof.elements.is_empty() {
+            return Err(MerkleError::EmptyAggregateProof);
+        }
+
+        if proof.siblings.len() > proof.directions.len() {
+            return Err(mismatched);
+        }
+
+        let minimized = proof.siblings.len() != proof.directions.len();
+        let mut siblings = proof.siblings.iter();
+        let mut directions = proof.directions.iter();
+        let mut boundaries = Vec::new();
+        // number of nodes the range covers at the current level
+        let mut width = proof.elements.len();
+
+        while let Some(&start_is_left_child) = directions.next() {
+            if width == 1 {
+                let sibling = siblings.next().ok_or(mismatched.to_owned())?;
+
+                boundaries.push(match start_is_left_child {
+                    true => (Some(sibling), None),
+                    false => (None, Some(sibling)),
+                });
+                continue;
+            }
+
+            let &end_is_right_child =
+                directions
+                    .next()
+                    .ok_or(MerkleError::UnpairedAggregateSiblings(
+                        proof.directions.len(),
+                    ))?;
+            let mut next_sibling = |used: bool| {
+                if used || !minimized {
+                    siblings.next().map(|sibling| used.then_some(sibling))
+                } else {
+                    Some(None)
+                }
+            };
+
+            let start_sibling = next_sibling(start_is_left_child).ok_or(mismatched.to_owned())?;
+            let end_sibling = next_sibling(end_is_right_child).ok_or(mismatched.to_owned())?;
+            boundaries.push((start_sibling, end_sibling));
+
+            width = ((start_is_left_child as usize + width - 1) >> 1) + 1;
+        }
+
+        if siblings.next().is_some() {
+            return Err(mismatched);
+        }
+
+        Ok(boundaries)
+    }
+
+    // verify an aggregate proof against a known root, first checking that it is well-formed,
+    // since `verify_aggregate_proof` treats any malformed proof as a failed one
+    pub fn verify_aggregate_proof_checked(
+        root: &RootHash,
+        proof: &MerkleAggregateProof,
+    ) -> Result<bool, MerkleError> {
+        aggregate_boundaries(proof)?;
+
+        Ok(verify_aggregate_proof(root, proof))
+    }
+
+    // verify an aggregate proof against a known root, returning the elements it covers in
+    // left-to-right order once they have been proven to be part of the tree
+    pub fn verify_aggregate_proof_elements(
+        root: &RootHash,
+        proof: &MerkleAggregateProof,
+    ) -> Result<Vec<String>, String> {
+        if !verify_aggregate_proof(root, proof) {
+            return Err(
+                "Aggregate proof does not reconstruct the given root, its elements are unverified"
+                    .to_string(),
+            );
+        }
+
+        Ok(proof.elements.to_owned())
+    }
+
+    // Generates a proof of the inclusion of an arbitrary set of elements, sharing the siblings
+    // their paths have in common and leaving out any node the verifier can derive itself.
+    // The indices may be given in any order and may repeat.
+    pub fn get_sparse_proof(
+        ref_tree: &MerkleTree,
+        indices: &[usize],
+    ) -> Result<SparseProof, String> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        if indices.is_empty() {
+            return Err("At least one index is needed for a sparse proof".to_string());
+        }
+
+        if ref_tree.weights.is_some() {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        if indices[indices.len() - 1] >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        let elements = indices
+            .iter()
+            .map(|&index| ref_tree.leaves[index].to_owned())
+            .collect::<_>();
+        let mut siblings = Vec::new();
+        let mut known = indices.to_owned();
+
+        for row in &ref_tree.levels[..ref_tree.height()] {
+            for (position, &index) in known.iter().enumerate() {
+                let sibling = index ^ 1;
+
+                // a left node is covered by the previous known node, a right one by the next
+                let sibling_is_known = if index % 2 == 1 {
+                    position > 0 && known[position - 1] == sibling
+                } else {
+                    known.get(position + 1) == Some(&sibling)
+                };
+
+                if !sibling_is_known && sibling < row.len() {
+                    siblings.push(row[sibling].to_owned());
+                }
+            }
+
+            known = known.iter().map(|index| index / 2).collect::<_>();
+            known.dedup();
+        }
+
+        Ok(SparseProof {
+            indices,
+            elements,
+            leaf_count: ref_tree.leaves.len(),
+            siblings,
+            config: ref_tree.config.to_owned(),
+        })
+    }
+
+    // verify a sparse proof against a known root, rebuilding each row from the nodes it knows
+    // and consuming the proof's siblings in the order `get_sparse_proof` emitted them
+    pub fn verify_sparse_proof(root: &RootHash, proof: &SparseProof) -> bool {
+        let config = &proof.config;
+
+        if proof.indices.is_empty()
+            || proof.indices.len() != proof.elements.len()
+            || proof.indices.windows(2).any(|pair| pair[0] >= pair[1])
+            || proof.indices[proof.indices.len() - 1] >= proof.leaf_count
+        {
+            return false;
+        }
+
+        let mut known = proof
+            .indices
+            .iter()
+            .zip(proof.elements.iter())
+            .map(|(&index, element)| (index, config.hash_leaf(element)))
+            .collect::<Vec<_>>();
+        let mut siblings = proof.siblings.iter();
+        let mut row_len = proof.leaf_count;
+
+        while row_len > 1 {
+            let mut parents: Vec<(usize, String)> = Vec::new();
+            let mut nodes = known.iter().peekable();
+
+            while let Some((index, hash)) = nodes.next() {
+                let parent = if index % 2 == 1 {
+                    match siblings.next() {
+                        Some(left) => config.hash_node(left, hash),
+                        None => return false,
+                    }
+                } else if nodes.peek().map(|(next, _)| *next) == Some(index + 1) {
+                    let (_, right) = nodes.next().expect("peeked the next known node");
+                    config.hash_node(hash, right)
+                } else if index + 1 < row_len {
+                    match siblings.next() {
+                        Some(right) => config.hash_node(hash, right),
+                        None => return false,
+                    }
+                } else {
+                    config.hash_node(hash, &config.odd_sibling_hash(hash))
+                };
+
+                parents.push((index / 2, parent));
+            }
+
+            known = parents;
+            row_len = row_len.div_ceil(2);
+        }
+
+        siblings.next().is_none() && known.len() == 1 && known[0].1 == root.as_str()
+    }
+
+    // proof of several disjoint ranges of elements at once, as a sparse proof over every index
+    // they cover, so siblings are shared between ranges wherever their paths meet
+    #[derive(Debug)]
+    pub struct MultiRangeProof {
+        pub(crate) ranges: Vec<(usize, usize)>, // sorted, disjoint [start, end) ranges of the proven elements
+        pub(crate) proof: SparseProof,          // sparse proof over every index of the ranges
+    }
+
+    impl MultiRangeProof {
+        pub fn ranges(&self) -> &[(usize, usize)] {
+            &self.ranges
+        }
+
+        // the elements of every range, in leaf order
+        pub fn elements(&self) -> &[String] {
+            &self.proof.elements
+        }
+    }
+
+    // return a proof of the elements in each of the `[start, end)` ranges, which must be
+    // non-empty, within the leaves and disjoint, though not necessarily in order
+    pub fn get_multi_range_proof(
+        ref_tree: &MerkleTree,
+        ranges: &[(usize, usize)],
+    ) -> Result<MultiRangeProof, String> {
+        let mut ranges = ranges.to_vec();
+        ranges.sort_unstable();
+
+        if let Some((start, end)) = ranges
+            .iter()
+            .find(|&&(start, end)| start >= end || end > ref_tree.leaves.len())
+        {
+            return Err(format!(
+                "Invalid range [{start}, {end}), ranges have to be non-empty and within the leaves"
+            ));
+        }
+
+        if let Some(pair) = ranges.windows(2).find(|pair| pair[0].1 > pair[1].0) {
+            return Err(format!(
+                "Ranges [{}, {}) and [{}, {}) overlap",
+                pair[0].0, pair[0].1, pair[1].0, pair[1].1
+            ));
+        }
+
+        let indices = ranges
+            .iter()
+            .flat_map(|&(start, end)| start..end)
+            .collect::<Vec<_>>();
+
+        Ok(MultiRangeProof {
+            proof: get_sparse_proof(ref_tree, &indices)?,
+            ranges,
+        })
+    }
+
+    // verify a multi-range proof against a known root, after checking that its sparse proof is
+    // over exactly the indices its ranges cover
+    pub fn verify_multi_range_proof(root: &RootHash, proof: &MultiRangeProof) -> bool {
+        proof
+            .ranges
+            .iter()
+            .flat_map(|&(start, end)| start..end)
+            .eq(proof.proof.indices.iter().copied())
+            && verify_sparse_proof(root, &proof.proof)
+    }
+
+    // a tree whose internal nodes have `arity` children rather than two, trading more siblings
+    // per level for fewer levels
+    //
+    // the leaves are padded with the padding element up to a multiple of the arity, and the
+    // last group of any other row is filled out with empty nodes, so an arity of 2 builds the
+    // same tree as `create_merkle_tree`; the odd handling, fixed depth and maximum depth of the
+    // configuration don't apply
+    #[derive(Debug)]
+    pub struct KaryMerkleTree {
+        pub(crate) leaves: Vec<String>,
+        pub(crate) root_hash: String,
+        pub(crate) arity: usize,
+        pub(crate) config: TreeConfig,
+        pub(crate) levels: Vec<Vec<String>>, // node hashes of every row, from the leaves up to the root
+    }
+
+    #[derive(Debug)]
+    pub struct KaryMerkleProof {
+        pub(crate) element: String, // element for which we want to prove inclusion
+        pub(crate) siblings: Vec<Vec<String>>, // the other arity-1 children of each node on the path, in order
+        pub(crate) positions: Vec<usize>, // position of the path's node among its siblings at each level
+        pub(crate) arity: usize,          // number of children of every internal node
+        pub(crate) config: TreeConfig,    // hashing scheme of the tree the proof was generated from
+    }
+
+    impl KaryMerkleTree {
+        pub fn leaves(&self) -> &[String] {
+            &self.leaves
+        }
+
+        pub fn arity(&self) -> usize {
+            self.arity
+        }
+
+        // number of levels above the leaves, and so the number of levels in every proof
+        pub fn height(&self) -> usize {
+            self.levels.len() - 1
+        }
+    }
+
+    pub fn create_karytree(elements: &[String], arity: usize) -> Result<KaryMerkleTree, String> {
+        create_karytree_with_config(elements, arity, TreeConfig::default())
+    }
+
+    pub fn create_karytree_with_config(
+        elements: &[String],
+        arity: usize,
+        config: TreeConfig,
+    ) -> Result<KaryMerkleTree, String> {
+        if arity < 2 {
+            return Err("A tree needs an arity of at least 2".to_string());
+        }
+
+        if elements.is_empty() {
+            return Err("A tree needs at least one element".to_string());
+        }
+
+        // the leaf limits apply to the padded leaves, which a wide arity can make far more
+        // numerous than the elements, and reserving them fails rather than aborting
+        let too_many = || format!("Too many leaves for a tree of arity {arity}");
+        let leaf_count = elements
+            .len()
+            .checked_next_multiple_of(arity)
+            .ok_or_else(too_many)?;
+        check_leaf_limits(leaf_count, &config)?;
+
+        let mut leaves = elements.to_owned();
+        leaves
+            .try_reserve_exact(leaf_count - elements.len())
+            .map_err(|_| too_many())?;
+        leaves.resize(leaf_count, config.padding.to_owned());
+
+        let mut levels = vec![leaves
+            .iter()
+            .map(|leaf| config.hash_leaf(leaf))
+            .collect::<Vec<_>>()];
+
+        while levels[levels.len() - 1].len() > 1 {
+            let row = &levels[levels.len() - 1];
+            let parents = row
+                .chunks(arity)
+                .map(|group| {
+                    let mut children = group.iter().map(String::as_str).collect::<Vec<_>>();
+                    children.resize(arity, "");
+                    config.hash_children(&children)
+                })
+                .collect::<Vec<_>>();
+
+            levels.push(parents);
+        }
+
+        Ok(KaryMerkleTree {
+            leaves,
+            root_hash: levels[levels.len() - 1][0].to_owned(),
+            arity,
+            config,
+            levels,
+        })
+    }
+
+    pub fn get_kary_root(ref_tree: &KaryMerkleTree) -> RootHash {
+        RootHash(ref_tree.root_hash.to_owned())
+    }
+
+    pub fn get_kary_proof(
+        ref_tree: &KaryMerkleTree,
+        index: usize,
+    ) -> Result<KaryMerkleProof, String> {
+        if index >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        let arity = ref_tree.arity;
+        let mut siblings = Vec::new();
+        let mut positions = Vec::new();
+        let mut current_index = index;
+
+        for row in &ref_tree.levels[..ref_tree.height()] {
+            let group_start = current_index - current_index % arity;
+
+            siblings.push(
+                (group_start..group_start + arity)
+                    .filter(|&i| i != current_index)
+                    .map(|i| row.get(i).cloned().unwrap_or_default())
+                    .collect::<Vec<_>>(),
+            );
+            positions.push(current_index % arity);
+
+            current_index /= arity;
+        }
+
+        Ok(KaryMerkleProof {
+            element: ref_tree.leaves[index].to_owned(),
+            siblings,
+            positions,
+            arity,
+            config: ref_tree.config.to_owned(),
+        })
+    }
+
+    // a k-ary proof as a `MerkleProof`, so that `verify_proof` handles it like a binary one,
+    // with the siblings of every level flattened and each marked as being left of the path
+    impl From<KaryMerkleProof> for MerkleProof {
+        fn from(proof: KaryMerkleProof) -> Self {
+            let directions = proof
+                .siblings
+                .iter()
+                .zip(proof.positions.iter())
+                .flat_map(|(siblings, &position)| (0..siblings.len()).map(move |i| i < position))
+                .collect::<Vec<_>>();
+
+            MerkleProof {
+                element: proof.element,
+                siblings: proof.siblings.into_iter().flatten().collect::<_>(),
+                directions,
+                config: proof.config,
+                weight: None,
+                arity: proof.arity,
+            }
+        }
+    }
+
+    // verify a k-ary proof against a known root, putting the path's node back among its
+    // siblings at each level before hashing them all together
+    pub fn verify_kary_proof(root: &RootHash, proof: &KaryMerkleProof) -> bool {
+        if proof.siblings.len() != proof.positions.len() {
+            return false;
+        }
+
+        let config = &proof.config;
+        let mut current_hash = config.hash_leaf(&proof.element);
+
+        for (siblings, &position) in proof.siblings.iter().zip(proof.positions.iter()) {
+            if siblings.len() + 1 != proof.arity || position >= proof.arity {
+                return false;
+            }
+
+            let mut children = siblings.iter().map(String::as_str).collect::<Vec<_>>();
+            children.insert(position, &current_hash);
+            current_hash = config.hash_children(&children);
+        }
+
+        current_hash == root.as_str()
+    }
+
+    // a merkle tree that only holds its leaves, hashing nodes the first time the root or a proof
+    // needs them and keeping them for later requests, for large leaf sets that are only ever
+    // proven at a handful of indices
+    //
+    // the siblings of a proof cover every leaf off its path, so the first proof still hashes
+    // most of the tree; what is saved is the nodes no request has needed so far, such as the
+    // ancestors shared by every proven index and the root itself
+    //
+    // roots and proofs are the same as those of `create_merkle_tree_with_config` with the same
+    // configuration; the tree is not `Sync`, since requests fill in the cache through `&self`
+    #[derive(Debug)]
+    pub struct LazyMerkleTree {
+        pub(crate) leaves: Vec<String>,
+        pub(crate) config: TreeConfig,
+        pub(crate) row_lens: Vec<usize>, // number of nodes in every row, from the leaves up to the root
+        pub(crate) nodes: RefCell<HashMap<(usize, usize), String>>, // hashes computed so far, by level and index
+    }
+
+    impl LazyMerkleTree {
+        pub fn leaves(&self) -> &[String] {
+            &self.leaves
+        }
+
+        // number of levels above the leaves, and so the number of siblings in every proof
+        pub fn height(&self) -> usize {
+            self.row_lens.len() - 1
+        }
+
+        // number of node hashes computed and cached so far
+        pub fn computed_nodes(&self) -> usize {
+            self.nodes.borrow().len()
+        }
+
+        fn node(&self, level: usize, index: usize) -> String {
+            if let Some(hash) = self.nodes.borrow().get(&(level, index)) {
+                return hash.to_owned();
+            }
+
+            let hash = if level == 0 {
+                self.config.hash_leaf(&self.leaves[index])
+            } else {
+                let left = self.node(level - 1, 2 * index);
+                let right = if 2 * index + 1 < self.row_lens[level - 1] {
+                    self.node(level - 1, 2 * index + 1)
+                } else {
+                    // the last node of an odd row, see `generate_parent_row`
+                    self.config.odd_sibling_hash(&left)
+                };
+
+                self.config.hash_node(&left, &right)
+            };
+
+            self.nodes
+                .borrow_mut()
+                .insert((level, index), hash.to_owned());
+
+            hash
+        }
+    }
+
+    pub fn create_lazy_merkle_tree(elements: &[String]) -> Result<LazyMerkleTree, String> {
+        create_lazy_merkle_tree_with_config(elements, TreeConfig::default())
+    }
+
+    pub fn create_lazy_merkle_tree_with_config(
+        elements: &[String],
+        config: TreeConfig,
+    ) -> Result<LazyMerkleTree, String> {
+        if elements.is_empty() {
+            return Err("A tree needs at least one element".to_string());
+        }
+
+        let leaves = pad_leaves(elements, &config)?;
+
+        Ok(LazyMerkleTree {
+            row_lens: row_lens(leaves.len()),
+            leaves,
+            config,
+            nodes: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn get_lazy_root(ref_tree: &LazyMerkleTree) -> RootHash {
+        RootHash(ref_tree.node(ref_tree.height(), 0))
+    }
+
+    pub fn get_lazy_proof(ref_tree: &LazyMerkleTree, index: usize) -> Result<MerkleProof, String> {
+        if index >= ref_tree.leaves.len() {
+            return Err("Index of the target element is out of bounds for this tree".to_string());
+        }
+
+        let (siblings, directions) = (0..ref_tree.height())
+            .map(|level| {
+                let position = index >> level;
+
+                if position % 2 == 1 {
+                    (ref_tree.node(level, position - 1), true)
+                } else if position + 1 < ref_tree.row_lens[level] {
+                    (ref_tree.node(level, position + 1), false)
+                } else {
+                    let last = ref_tree.node(level, position);
+                    (ref_tree.config.odd_sibling_hash(&last), false)
+                }
+            })
+            .unzip();
+
+        Ok(MerkleProof {
+            element: ref_tree.leaves[index].to_owned(),
+            siblings,
+            directions,
+            config: ref_tree.config.to_owned(),
+            weight: None,
+            arity: 2,
+        })
+    }
+
+    // a merkle tree committing to key-value pairs, with every leaf hashed by `hash_kv_leaf`
+    //
+    // the pairs are sorted by key so that any two parties holding the same pairs agree on the
+    // root whatever order they were supplied in, which is also why keys have to be unique; there
+    // are no padding leaves, the last leaf of an odd row is paired like any other odd node
+    #[derive(Debug)]
+    pub struct KvMerkleTree {
+        pub(crate) pairs: Vec<(String, String)>, // sorted by key
+        pub(crate) root_hash: String,
+        pub(crate) config: TreeConfig,
+        pub(crate) levels: Vec<Vec<String>>, // node hashes of every row, from the leaves up to the root
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct KvMerkleProof {
+        pub(crate) key: String,
+        pub(crate) value: String,
+        pub(crate) siblings: Vec<String>, // path of siblings from the pair up to the root
+        pub(crate) directions: Vec<bool>, // signal if the sibling at the same index is on the left or right
+        pub(crate) config: TreeConfig,    // hashing scheme of the tree the proof was generated from
+    }
+
+    impl KvMerkleTree {
+        // the committed pairs, sorted by key
+        pub fn pairs(&self) -> &[(String, String)] {
+            &self.pairs
+        }
+    }
+
+    impl KvMerkleProof {
+        pub fn key(&self) -> &str {
+            &self.key
+        }
+
+        // the value the proof claims for its key, only proven once the proof verifies
+        pub fn value(&self) -> &str {
+            &self.value
+        }
+    }
+
+    pub fn create_kv_merkle_tree(pairs: &[(String, String)]) -> Result<KvMerkleTree, String> {
+        create_kv_merkle_tree_with_config(pairs, TreeConfig::default())
+    }
+
+    pub fn create_kv_merkle_tree_with_config(
+        pairs: &[(String, String)],
+        config: TreeConfig,
+    ) -> Result<KvMerkleTree, String> {
+        if pairs.is_empty() {
+            return Err("A tree needs at least one element".to_string());
+        }
+
+        check_leaf_limits(pairs.len(), &config)?;
+        check_depth_limit(pairs.len(), &config)?;
+
+        let mut pairs = pairs.to_vec();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        if let Some(pair) = pairs.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("duplicate key {:?}", pair[0].0));
+        }
+
+        let leaf_row = pairs
+            .iter()
+            .map(|(key, value)| MerkleNode {
+                value: config.hash_kv_leaf(key, value),
+                left: None,
+                right: None,
+            })
+            .collect::<Vec<_>>();
+        let levels = levels_from_row(leaf_row, &config);
+
+        Ok(KvMerkleTree {
+            pairs,
+            root_hash: levels[levels.len() - 1][0].to_owned(),
+            config,
+            levels,
+        })
+    }
+
+    pub fn get_kv_root(ref_tree: &KvMerkleTree) -> RootHash {
+        RootHash(ref_tree.root_hash.to_owned())
+    }
+
+    // return a proof of the value committed for the given key
+    pub fn get_kv_proof(ref_tree: &KvMerkleTree, key: &str) -> Result<KvMerkleProof, String> {
+        let index = ref_tree
+            .pairs
+            .binary_search_by(|(k, _)| k.as_str().cmp(key))
+            .map_err(|_| format!("key {key:?} is not part of this tree"))?;
+
+        let (siblings, directions) = ref_tree.levels[..ref_tree.levels.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(level, row)| {
+                let (sibling, side) = sibling_in_row(row, index >> level, &ref_tree.config);
+                (sibling, side == Side::Left)
+            })
+            .unzip();
+
+        let (key, value) = ref_tree.pairs[index].to_owned();
+
+        Ok(KvMerkleProof {
+            key,
+            value,
+            siblings,
+            directions,
+            config: ref_tree.config.to_owned(),
+        })
+    }
+
+    // verify that the key is bound to the value in the tree with the given root
+    pub fn verify_kv_proof(root: &RootHash, proof: &KvMerkleProof) -> bool {
+        proof.siblings.len() == proof.directions.len()
+            && fold_siblings(
+                &proof.config,
+                proof.config.hash_kv_leaf(&proof.key, &proof.value),
+                &proof.siblings,
+                &proof.directions,
+            )
+            .eq(root.as_str())
+    }
+
+    // the right edge of a tree, enough to keep appending elements to it and computing its root
+    // without holding its leaves, as an append-only log would
+    //
+    // the frontier keeps, for every set bit `l` of the element count, the hash of the complete
+    // subtree of 2^l elements at the end of the elements, like the digits of a binary counter;
+    // appending an element carries through the levels the same way incrementing the counter does
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct MerkleFrontier {
+        pub(crate) nodes: Vec<Option<String>>, // subtree hash of every level, if its bit of the count is set
+        pub(crate) element_count: usize,
+        pub(crate) config: TreeConfig,
+    }
+
+    impl MerkleFrontier {
+        pub fn element_count(&self) -> usize {
+            self.element_count
+        }
+
+        pub fn append(&mut self, element: &str) {
+            let mut current_hash = self.config.hash_leaf(element);
+            let mut level = 0;
+
+            while self.element_count >> level & 1 == 1 {
+                let left = self.nodes[level].take().expect("set bits have a subtree");
+                current_hash = self.config.hash_node(&left, &current_hash);
+                level += 1;
+            }
+
+            if level == self.nodes.len() {
+                self.nodes.push(None);
+            }
+
+            self.nodes[level] = Some(current_hash);
+            self.element_count += 1;
+        }
+
+        // the root of the tree `create_merkle_tree_with_config` would build over every element
+        // appended so far, padding included
+        //
+        // each level is left with at most one node that isn't a complete subtree, on the right
+        // edge, which is paired with the subtree of the level if there is one, or as the last
+        // node of an odd row otherwise
+        pub fn root(&self) -> RootHash {
+            let config = &self.config;
+
+            // the padding leaf of an odd number of elements is the only partial node of the leaves
+            let mut partial = (self.element_count % 2 == 1).then(|| match config.odd_handling {
+                OddHandling::PadEmpty => config.hash_leaf(&config.padding),
+                OddHandling::DuplicateLast => {
+                    self.nodes[0].to_owned().expect("set bits have a subtree")
+                }
+            });
+
+            let mut level = 0;
+
+            loop {
+                let complete = self.element_count >> level;
+                let subtree = self.nodes.get(level).and_then(Option::as_ref);
+
+                partial = match (subtree, partial) {
+                    (Some(root), None) if complete == 1 => return RootHash(root.to_owned()),
+                    (None, Some(root)) if complete == 0 => return RootHash(root),
+                    (Some(left), Some(right)) => Some(config.hash_node(left, &right)),
+                    (Some(last), None) => {
+                        Some(config.hash_node(last, &config.odd_sibling_hash(last)))
+                    }
+                    (None, Some(last)) => {
+                        Some(config.hash_node(&last, &config.odd_sibling_hash(&last)))
+                    }
+                    (None, None) => None,
+                };
+
+                level += 1;
+            }
+        }
+    }
+
+    // the right edge of a tree, one subtree hash per set bit of its element count from the
+    // lowest up, which together with the element count is all `resume_from_frontier` needs
+    //
+    // weighted trees and trees of fixed depth can't be resumed, since appending to them needs
+    // weights or the padding of the whole tree
+    pub fn frontier(ref_tree: &MerkleTree) -> Result<Vec<String>, String> {
+        if ref_tree.weights.is_some() {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        if ref_tree.config.fixed_depth.is_some() {
+            return Err("Trees of fixed depth have no frontier to resume from".to_string());
+        }
+
+        let element_count = ref_tree.element_count;
+
+        Ok((0..usize::BITS as usize)
+            .filter(|&level| element_count >> level & 1 == 1)
+            .map(|level| ref_tree.levels[level][(element_count >> level) - 1].to_owned())
+            .collect::<_>())
+    }
+
+    // pick up a tree from its frontier and element count, to keep appending to it
+    pub fn resume_from_frontier(
+        frontier: &[String],
+        real_leaf_count: usize,
+    ) -> Result<MerkleFrontier, String> {
+        resume_from_frontier_with_config(frontier, real_leaf_count, TreeConfig::default())
+    }
+
+    // `resume_from_frontier` for a tree built with a non-default configuration
+    pub fn resume_from_frontier_with_config(
+        frontier: &[String],
+        real_leaf_count: usize,
+        config: TreeConfig,
+    ) -> Result<MerkleFrontier, String> {
+        if real_leaf_count == 0 {
+            return Err("A tree needs at least one element".to_string());
+        }
+
+        if config.fixed_depth.is_some() {
+            return Err("Trees of fixed depth have no frontier to resume from".to_string());
+        }
+
+        if frontier.len() != real_leaf_count.count_ones() as usize {
+            return Err(format!(
+                "A frontier of {real_leaf_count} elements has {} subtrees, not {}",
+                real_leaf_count.count_ones(),
+                frontier.len()
+            ));
+        }
+
+        let mut subtrees = frontier.iter().cloned();
+        let nodes = (0..usize::BITS - real_leaf_count.leading_zeros())
+            .map(|level| match real_leaf_count >> level & 1 {
+                1 => subtrees.next(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(MerkleFrontier {
+            nodes,
+            element_count: real_leaf_count,
+            config,
+        })
+    }
+
+    // the root of every prefix of the elements, from the first element alone up to all of them,
+    // as the running commitment of an append log
+    //
+    // the elements are appended to a frontier one at a time, so each root costs O(log(n))
+    // hashes rather than a rebuild of the prefix
+    pub fn prefix_roots(elements: &[String]) -> Vec<String> {
+        let mut frontier = MerkleFrontier {
+            nodes: Vec::new(),
+            element_count: 0,
+            config: TreeConfig::default(),
+        };
+
+        elements
+            .iter()
+            .map(|element| {
+                frontier.append(element);
+                frontier.root().0
+            })
+            .collect::<_>()
+    }
+
+    // a proof that a tree's elements are the first elements of a larger one, as a tree grows by
+    // appending, in the spirit of Certificate Transparency's consistency proofs
+    //
+    // the old tree's frontier is the complete subtrees covering exactly its elements, and
+    // reproduces its root; each of those subtrees is also a node of the new tree, at a position
+    // fixed by the old element count, so a path from each of them up to the new root shows the
+    // new tree holds every old element at the same index
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct PrefixProof {
+        pub(crate) old_count: usize,        // number of elements of the old tree
+        pub(crate) new_count: usize,        // number of elements of the new tree
+        pub(crate) frontier: Vec<String>, // complete subtrees of the old tree, as returned by `frontier`
+        pub(crate) paths: Vec<Vec<String>>, // siblings of each frontier subtree up to the root of the new tree
+        pub(crate) config: TreeConfig,      // hashing scheme of both trees
+    }
+
+    // the level and index of each subtree of the frontier of `element_count` elements, in the
+    // order `frontier` returns them
+    fn frontier_positions(element_count: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..usize::BITS as usize)
+            .filter(move |&level| element_count >> level & 1 == 1)
+            .map(move |level| (level, (element_count >> level) - 1))
+    }
+
+    // prove that `old` holds the first elements of `new`, as when `new` was built by appending
+    // to `old`; weighted trees and trees of fixed depth have no frontier, so can't be proven
+    pub fn prove_prefix(old: &MerkleTree, new: &MerkleTree) -> Result<PrefixProof, String> {
+        if old.config != new.config {
+            return Err("Trees are hashed with different configurations".to_string());
+        }
+
+        if new.weights.is_some() {
+            return Err(WEIGHTED_TREE_UNSUPPORTED.to_string());
+        }
+
+        if old.element_count > new.element_count {
+            return Err("The old tree has more elements than the new one".to_string());
+        }
+
+        let old_frontier = frontier(old)?;
+        let mut paths = Vec::new();
+
+        for ((level, index), subtree) in frontier_positions(old.element_count).zip(&old_frontier) {
+            if new.levels[level][index] != *subtree {
+                return Err("The old tree is not a prefix of the new one".to_string());
+            }
+
+            paths.push(
+                (level..new.height())
+                    .map(|row| {
+                        sibling_in_row(&new.levels[row], index >> (row - level), &new.config).0
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        Ok(PrefixProof {
+            old_count: old.element_count,
+            new_count: new.element_count,
+            frontier: old_frontier,
+            paths,
+            config: new.config.to_owned(),
+        })
+    }
+
+    // verify that the tree with root `old_root` holds the first elements of the tree with root
+    // `new_root`, the positions of the subtrees being derived from the element counts rather
+    // than taken from the proof
+    pub fn verify_prefix_proof(
+        old_root: &RootHash,
+        new_root: &RootHash,
+        proof: &PrefixProof,
+    ) -> bool {
+        if proof.old_count > proof.new_count || proof.paths.len() != proof.frontier.len() {
+            return false;
+        }
+
+        let reproduces_old_root = resume_from_frontier_with_config(
+            &proof.frontier,
+            proof.old_count,
+            proof.config.to_owned(),
+        )
+        .is_ok_and(|resumed| resumed.root() == *old_root);
+
+        if !reproduces_old_root {
+            return false;
+        }
+
+        let height = required_height(proof.new_count);
+
+        frontier_positions(proof.old_count)
+            .zip(proof.frontier.iter().zip(proof.paths.iter()))
+            .all(|((level, index), (subtree, path))| {
+                let directions = (0..path.len())
+                    .map(|step| index >> step & 1 == 1)
+                    .collect::<Vec<_>>();
+
+                level + path.len() == height
+                    && fold_siblings(&proof.config, subtree.to_owned(), path, &directions)
+                        .eq(new_root.as_str())
+            })
+    }
+
+    // a tree of fixed depth over few elements, whose proofs leave out the siblings that are
+    // empty subtrees, made of padding alone
+    //
+    // an empty subtree hashes the same at every position of a level, so the verifier
+    // regenerates those siblings from the configuration instead of being sent them; a proof of
+    // an element in a deep, mostly empty tree carries little more than the element itself
+    //
+    // the tree itself only keeps the nodes over its elements, the empty subtrees right of them
+    // standing in for the padding, so building one costs as much as a tree over the elements
+    // alone whatever its depth
+    #[derive(Debug)]
+    pub struct SparseMerkleTree {
+        pub(crate) elements: Vec<String>, // elements of the leftmost leaves, every other leaf being padding
+        pub(crate) levels: Vec<Vec<String>>, // hashes of the nodes over the elements on every level, from the leaves up to the root
+        pub(crate) empty_hashes: Vec<String>, // hash of an empty subtree of every level, from a padding leaf up
+        pub(crate) config: TreeConfig, // hashing scheme of the tree, with its depth as the fixed depth
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct CompressedProof {
+        pub(crate) element: String, // element for which we want to prove inclusion
+        pub(crate) siblings: Vec<String>, // siblings that aren't empty subtrees, from the element up
+        pub(crate) omitted: Vec<bool>, // signal if the sibling at each level is an empty subtree left out of `siblings`
+        pub(crate) directions: Vec<bool>, // signal if the sibling at each level is on the left or right
+        pub(crate) config: TreeConfig,    // hashing scheme of the tree the proof was generated from
+    }
+
+    impl SparseMerkleTree {
+        // number of levels above the leaves, the length of every proof
+        pub fn depth(&self) -> usize {
+            self.levels.len() - 1
+        }
+
+        pub fn element_count(&self) -> usize {
+            self.elements.len()
+        }
+    }
+
+    impl CompressedProof {
+        // number of siblings the proof actually carries
+        pub fn explicit_siblings(&self) -> usize {
+            self.siblings.len()
+        }
+    }
+
+    // hashes of the empty subtrees of every level up to `depth`, from a single padding leaf
+    fn empty_subtree_hashes(config: &TreeConfig, depth: usize) -> Vec<String> {
+        let mut empty_hashes = vec![config.hash_leaf(&config.padding)];
+
+        for level in 0..depth {
+            let below = &empty_hashes[level];
+            empty_hashes.push(config.hash_node(below, below));
+        }
+
+        empty_hashes
+    }
+
+    pub fn create_sparse_merkle_tree(
+        elements: &[String],
+        depth: usize,
+    ) -> Result<SparseMerkleTree, String> {
+        create_sparse_merkle_tree_with_config(elements, depth, TreeConfig::default())
+    }
+
+    // `create_sparse_merkle_tree` hashed according to the given configuration, whose own depth
+    // is replaced with `depth`
+    pub fn create_sparse_merkle_tree_with_config(
+        elements: &[String],
+        depth: usize,
+        config: TreeConfig,
+    ) -> Result<SparseMerkleTree, String> {
+        let config = config.with_fixed_depth(depth);
+        check_leaf_limits(elements.len(), &config)?;
+        check_depth_limit(elements.len(), &config)?;
+
+        let fits = u32::try_from(depth)
+            .ok()
+            .and_then(|depth| 1usize.checked_shl(depth))
+            .is_none_or(|capacity| capacity >= elements.len());
+
+        if !fits {
+            return Err(format!(
+                "Too many elements for a tree of fixed depth {depth}"
+            ));
+        }
+
+        let empty_hashes = empty_subtree_hashes(&config, depth);
+        let mut levels = vec![elements
+            .iter()
+            .map(|element| config.hash_leaf(element))
+            .collect::<Vec<_>>()];
+
+        // a node without a right sibling among the elements' nodes is paired with the empty
+        // subtree of its level, as it would be with every padding leaf in place
+        for empty_hash in &empty_hashes[..depth] {
+            let parents = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| config.hash_node(&pair[0], pair.get(1).unwrap_or(empty_hash)))
+                .collect::<Vec<_>>();
+            levels.push(parents);
+        }
+
+        Ok(SparseMerkleTree {
+            elements: elements.to_vec(),
+            levels,
+            empty_hashes,
+            config,
+        })
+    }
+
+    // the root of a tree without elements is the empty subtree of its full depth
+    pub fn get_sparse_tree_root(ref_tree: &SparseMerkleTree) -> RootHash {
+        let depth = ref_tree.depth();
+
+        RootHash(
+            ref_tree.levels[depth]
+                .first()
+                .unwrap_or(&ref_tree.empty_hashes[depth])
+                .to_owned(),
+        )
+    }
+
+    // return a proof of the element at the given index, leaving out every sibling that is an
+    // empty subtree
+    pub fn get_compressed_proof(
+        ref_tree: &SparseMerkleTree,
+        index: usize,
+    ) -> Result<CompressedProof, String> {
+        let element = ref_tree.elements.get(index).ok_or_else(|| {
+            "Index of the target element is out of bounds for this tree".to_string()
+        })?;
+
+        let mut siblings = Vec::new();
+        let (omitted, directions) = ref_tree.levels[..ref_tree.depth()]
+            .iter()
+            .zip(&ref_tree.empty_hashes)
+            .enumerate()
+            .map(|(level, (row, empty_hash))| {
+                let position = index >> level;
+                // a sibling equal to the empty subtree is left out even when it is the node of
+                // an element, as the verifier regenerates the same hash either way
+                let sibling = row
+                    .get(position ^ 1)
+                    .filter(|&sibling| sibling != empty_hash);
+
+                if let Some(sibling) = sibling {
+                    siblings.push(sibling.to_owned());
+                }
+
+                (sibling.is_none(), position % 2 == 1)
+            })
+            .unzip();
+
+        Ok(CompressedProof {
+            element: element.to_owned(),
+            siblings,
+            omitted,
+            directions,
+            config: ref_tree.config.to_owned(),
+        })
+    }
+
+    // verify a compressed proof against a known root, putting the empty subtrees it left out
+    // back in before folding the siblings as `verify_proof` does
+    pub fn verify_compressed_proof(root: &RootHash, proof: &CompressedProof) -> bool {
+        let depth = proof.omitted.len();
+
+        if proof.directions.len() != depth
+            || proof.omitted.iter().filter(|&&omitted| !omitted).count() != proof.siblings.len()
+        {
+            return false;
+        }
+
+        let empty_hashes = empty_subtree_hashes(&proof.config, depth);
+        let mut explicit = proof.siblings.iter();
+        let siblings = proof
+            .omitted
+            .iter()
+            .zip(&empty_hashes)
+            .map(|(&omitted, empty_hash)| match omitted {
+                true => empty_hash.to_owned(),
+                false => explicit.next().expect("counted above").to_owned(),
+            })
+            .collect::<Vec<_>>();
+
+        fold_siblings(
+            &proof.config,
+            proof.config.hash_leaf(&proof.element),
+            &siblings,
+            &proof.directions,
+        )
+        .eq(root.as_str())
+    }
+
+    // a merkle mountain range, an append-only commitment kept as a row of perfect subtrees, the
+    // mountains, of strictly decreasing height; appending merges the mountains of equal height
+    // the way incrementing carries through a binary counter, so nothing committed is rehashed
+    pub mod mmr {
+        use super::{hash_leaf, hash_node, RootHash};
+
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        pub struct MMR {
+            mountains: Vec<Vec<Vec<String>>>, // hashes of every row of each mountain, from the leaves up to its peak
+            element_count: usize,
+        }
+
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        pub struct MmrProof {
+            pub(crate) siblings: Vec<String>, // path of siblings from the element up to the peak of its mountain
+            pub(crate) directions: Vec<bool>, // signal if the sibling at the same index is on the left or right
+            pub(crate) peaks: Vec<String>,    // peaks of every other mountain, left to right
+            pub(crate) mountain: usize,       // position of the element's mountain among the peaks
+        }
+
+        impl MMR {
+            pub fn new() -> MMR {
+                MMR::default()
+            }
+
+            pub fn element_count(&self) -> usize {
+                self.element_count
+            }
+
+            pub fn append(&mut self, element: &str) {
+                // every trailing set bit of the count is a mountain the new one merges into
+                let merges = self.element_count.trailing_ones();
+                self.mountains.push(vec![vec![hash_leaf(element)]]);
+                self.element_count += 1;
+
+                for _ in 0..merges {
+                    let right = self.mountains.pop().expect("merges leave two mountains");
+                    let left = self.mountains.pop().expect("merges leave two mountains");
+                    let peak = hash_node(&left[left.len() - 1][0], &right[right.len() - 1][0]);
+
+                    let mut merged = left
+                        .into_iter()
+                        .zip(right)
+                        .map(|(mut left_row, right_row)| {
+                            left_row.extend(right_row);
+                            left_row
+                        })
+                        .collect::<Vec<_>>();
+                    merged.push(vec![peak]);
+
+                    self.mountains.push(merged);
+                }
+            }
+
+            fn peaks(&self) -> Vec<String> {
+                self.mountains
+                    .iter()
+                    .map(|levels| levels[levels.len() - 1][0].to_owned())
+                    .collect()
+            }
+
+            // bag the peaks from the right, so an MMR of a power of two elements has the same
+            // root as a tree over them
+            pub fn root(&self) -> Result<RootHash, String> {
+                bag_peaks(&self.peaks())
+                    .map(RootHash)
+                    .ok_or_else(|| "An empty MMR has no root".to_string())
+            }
+
+            pub fn proof(&self, index: usize) -> Result<MmrProof, String> {
+                if index >= self.element_count {
+                    return Err(String::from(
+                        "Index of the target element is out of bounds for this MMR",
+                    ));
+                }
+
+                let mut position = index;
+                let mut mountain = 0;
+
+                while position >= self.mountains[mountain][0].len() {
+                    position -= self.mountains[mountain][0].len();
+                    mountain += 1;
+                }
+
+                let levels = &self.mountains[mountain];
+                let (siblings, directions) = levels[..levels.len() - 1]
+                    .iter()
+                    .enumerate()
+                    .map(|(level, row)| {
+                        let level_position = position >> level;
+                        (row[level_position ^ 1].to_owned(), level_position % 2 == 1)
+                    })
+                    .unzip();
+
+                let mut peaks = self.peaks();
+                peaks.remove(mountain);
+
+                Ok(MmrProof {
+                    siblings,
+                    directions,
+                    peaks,
+                    mountain,
+                })
+            }
+
+            // verify that `element` was appended to the MMR with the given root, as of the
+            // appends the proof was generated after
+            pub fn verify(root: &RootHash, element: &str, proof: &MmrProof) -> bool {
+                if proof.siblings.len() != proof.directions.len()
+                    || proof.mountain > proof.peaks.len()
+                {
+                    return false;
+                }
+
+                let peak = proof.siblings.iter().zip(proof.directions.iter()).fold(
+                    hash_leaf(element),
+                    |current, (sibling, is_left_child)| match is_left_child {
+                        true => hash_node(sibling, &current),
+                        false => hash_node(&current, sibling),
+                    },
+                );
+
+                let mut peaks = proof.peaks.to_owned();
+                peaks.insert(proof.mountain, peak);
+
+                bag_peaks(&peaks).is_some_and(|bagged| root.eq(&bagged))
+            }
+        }
+
+        fn bag_peaks(peaks: &[String]) -> Option<String> {
+            peaks
+                .iter()
+                .rev()
+                .cloned()
+                .reduce(|right, left| hash_node(&left, &right))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod validations {
+    use crate::merkle_tree::*;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+    use std::thread;
+
+    const TEST_ELEMENTS: [&str; 3] = ["some", "test", "elements"];
+    const MORE_TEST_ELEMENTS: [&str; 4] = ["some", "more", "test", "elements"];
+    const EVEN_MORE_TEST_ELEMENTS: [&str; 5] = ["some", "more", "valid", "test", "elements"];
+    const YET_MORE_TEST_ELEMENTS: [&str; 6] = ["some", "more", "valid", "test", "elements", "too"];
+    const LOTS_MORE_TEST_ELEMENTS: [&str; 7] =
+        ["some", "more", "valid", "test", "elements", "to", "use"];
+    const INCREASINGLY_MORE_TEST_ELEMENTS: [&str; 8] = [
+        "some", "more", "valid", "test", "elements", "to", "use", "again",
+    ];
+    const INVALID_HASH: &str = "not_a_valid_hash";
+    const WRONG_ROOT_HASH: &str =
+        "0000000000000000000000000000000000000000000000000000000000000000";
+    const VERIFY_PROOF_FAILED: bool = false;
+
+    fn get_test_tree(input: Vec<&str>) -> MerkleTree {
+        let elements = input.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given const test inputs")
+    }
+
+    fn get_wrong_root() -> RootHash {
+        RootHash::try_from(WRONG_ROOT_HASH).expect("Should have accepted a well-formed root hash")
+    }
+
+    fn get_expected_root_hash(input: Vec<&str>) -> String {
+        let mut leaves = input;
+        if leaves.len() % 2 == 1 {
+            leaves.push("");
+        }
+
+        let mut nodes: Vec<String> = leaves.iter().map(|e| hash_leaf(e)).collect::<_>();
+
+        while nodes.len() > 1 {
+            let head: Vec<String> = nodes
+                .chunks_exact(2)
+                .map(|pair| hash_node(&pair[0], &pair[1]))
+                .collect::<_>();
+
+            let tail: Vec<String> = nodes
+                .chunks_exact(2)
+                .remainder()
+                .iter()
+                .map(|s| hash_node(s, ""))
+                .collect::<_>();
+
+            nodes = head.into_iter().chain(tail).collect::<Vec<_>>();
+        }
+
+        nodes[0].to_owned()
+    }
+
+    #[test]
+    fn getting_root_hashes() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let expected_root_hash = mt.root_hash.to_owned();
+
+        assert_eq!(get_root(&mt), expected_root_hash);
+    }
+
+    #[test]
+    fn verifying_proofs() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+
+        let proof =
+            get_proof(&mt, 0).expect("Should have received a valid proof for the first element");
+
+        assert!(verify_proof(&get_root(&mt), &proof));
+        assert_eq!(verify_proof(&get_wrong_root(), &proof), VERIFY_PROOF_FAILED);
+    }
+
+    #[test]
+    fn updating_elements() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let new_element = "extra";
+        let mut elements = TEST_ELEMENTS.to_vec();
+        elements.insert(1, new_element);
+        let expected_root = get_expected_root_hash(elements);
+
+        let updated_mt = update_element(mt, 1, new_element).expect(
+            "Should have received a valid tree from the implementation given these known inputs",
+        );
+
+        assert_eq!(get_root(&updated_mt), expected_root);
+    }
+
+    #[test]
+    fn updating_elements_out_of_bounds() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let new_element = "and this is what it means to go even further beyond!";
+
+        let oob = mt.leaves.len();
+        let result = update_element(mt, oob, new_element);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn generating_trees_of_varying_heights() {
+        let mt_expected_root = get_expected_root_hash(TEST_ELEMENTS.to_vec());
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+
+        let more_mt_expected_root = get_expected_root_hash(MORE_TEST_ELEMENTS.to_vec());
+        let more_mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+
+        let even_more_mt_expected_root = get_expected_root_hash(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let even_more_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+
+        let yet_more_mt_expected_root = get_expected_root_hash(YET_MORE_TEST_ELEMENTS.to_vec());
+        let yet_more_mt = get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec());
+
+        let lots_more_mt_expected_root = get_expected_root_hash(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let lots_more_mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        let increasingly_more_mt_expected_root =
+            get_expected_root_hash(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let increasingly_more_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        assert_eq!(get_root(&mt), mt_expected_root);
+        assert_eq!(get_root(&more_mt), more_mt_expected_root);
+        assert_eq!(get_root(&even_more_mt), even_more_mt_expected_root);
+        assert_eq!(get_root(&yet_more_mt), yet_more_mt_expected_root);
+        assert_eq!(get_root(&lots_more_mt), lots_more_mt_expected_root);
+        assert_eq!(
+            get_root(&increasingly_more_mt),
+            increasingly_more_mt_expected_root
+        );
+    }
+
+    #[test]
+    fn verifying_aggregate_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_aggregate_proof(&mt, 2, 6)
+            .expect("Should have received a valid proof for the elements [2,6)");
+
+        assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+        assert_eq!(
+            verify_aggregate_proof(&get_wrong_root(), &proof),
+            VERIFY_PROOF_FAILED
+        );
+    }
+
+    #[test]
+    fn concatenating_aggregate_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        for (start, split, end) in [(0, 4, 8), (1, 3, 6), (2, 5, 7), (6, 7, 8)] {
+            let left = get_aggregate_proof(&mt, start, split)
+                .expect("Should have received a valid proof for the left range");
+            let right = get_aggregate_proof(&mt, split, end)
+                .expect("Should have received a valid proof for the right range");
+
+            let combined = concat_aggregate_proofs(&left, &right.minimize())
+                .expect("Should have combined the proofs of adjacent ranges");
+
+            assert!(verify_aggregate_proof(&get_root(&mt), &combined));
+            assert_eq!(
+                combined,
+                get_aggregate_proof(&mt, start, end)
+                    .expect("Should have received a valid proof for the whole range")
+            );
+        }
+
+        let odd_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let left = get_aggregate_proof(&odd_mt, 1, 4)
+            .expect("Should have received a valid proof for the left range");
+        let right = get_aggregate_proof(&odd_mt, 4, 5)
+            .expect("Should have received a valid proof for the right range");
+        let combined = concat_aggregate_proofs(&left, &right)
+            .expect("Should have combined the proofs of adjacent ranges");
+        assert!(verify_aggregate_proof(&get_root(&odd_mt), &combined));
+
+        let gap = get_aggregate_proof(&mt, 5, 7)
+            .expect("Should have received a valid proof for the elements [5,7)");
+        let first = get_aggregate_proof(&mt, 0, 4)
+            .expect("Should have received a valid proof for the elements [0,4)");
+        assert!(concat_aggregate_proofs(&first, &gap).is_err());
+        assert!(concat_aggregate_proofs(&gap, &first).is_err());
+    }
+
+    #[test]
+    fn verifying_full_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_full_proof(&mt).expect("Should have received a proof for every leaf");
+
+        assert_eq!(proof.elements(), mt.leaves());
+        assert!(proof.siblings.is_empty());
+        assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+        assert!(!verify_aggregate_proof(&get_wrong_root(), &proof));
+
+        // the padding leaf, or the empty node above it, is all the siblings the elements need
+        let padded_mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let padded_proof =
+            get_full_proof(&padded_mt).expect("Should have received a proof for every element");
+
+        assert_eq!(padded_proof.elements(), LOTS_MORE_TEST_ELEMENTS);
+        assert!(padded_proof.siblings.contains(&hash_leaf("")));
+        assert!(padded_proof
+            .siblings
+            .iter()
+            .all(|sibling| sibling.is_empty() || *sibling == hash_leaf("")));
+        assert!(verify_aggregate_proof(&get_root(&padded_mt), &padded_proof));
+
+        let weighted_mt = create_weighted_merkle_tree(&[("some".to_string(), 1)])
+            .expect("Should have received a valid tree given const test inputs");
+        assert!(get_full_proof(&weighted_mt).is_err());
+    }
+
+    #[test]
+    fn verifying_multi_range_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_multi_range_proof(&mt, &[(5, 7), (0, 2)])
+            .expect("Should have received a valid proof for the ranges [0,2) and [5,7)");
+
+        assert_eq!(proof.ranges(), [(0, 2), (5, 7)]);
+        assert_eq!(proof.elements(), ["some", "more", "to", "use"]);
+        assert!(verify_multi_range_proof(&get_root(&mt), &proof));
+        assert!(!verify_multi_range_proof(&get_wrong_root(), &proof));
+
+        let mut shifted_proof = get_multi_range_proof(&mt, &[(0, 2), (5, 7)])
+            .expect("Should have received a valid proof for the ranges [0,2) and [5,7)");
+        shifted_proof.ranges[1] = (4, 6);
+        assert!(!verify_multi_range_proof(&get_root(&mt), &shifted_proof));
+
+        assert!(get_multi_range_proof(&mt, &[(0, 3), (2, 4)]).is_err());
+        assert!(get_multi_range_proof(&mt, &[(3, 3)]).is_err());
+        assert!(get_multi_range_proof(&mt, &[(6, 9)]).is_err());
+        assert!(get_multi_range_proof(&mt, &[]).is_err());
+    }
+
+    #[test]
+    fn verifying_aggregate_proofs_out_of_bounds() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let oob = mt.leaves.len() + 1;
+        let overflow_result = get_aggregate_proof(&mt, 0, oob);
+        let invert_result = get_aggregate_proof(&mt, 1, 0);
+        let eq_result = get_aggregate_proof(&mt, 2, 2);
+
+        assert!(overflow_result.is_err());
+        assert!(invert_result.is_err());
+        assert!(eq_result.is_err());
+    }
+
+    #[test]
+    fn test_root() {
+        let expected_root = get_expected_root_hash(TEST_ELEMENTS.to_vec());
+
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+
+        assert_eq!(get_root(&mt), expected_root);
+    }
+
+    #[test]
+    fn test_proof() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+
+        for i in 0..TEST_ELEMENTS.len() {
+            let proof = get_proof(&mt, i)
+                .expect("Should have received a valid proof for any of the original elements");
+
+            assert!(verify_proof(&get_root(&mt), &proof))
+        }
+    }
+
+    #[test]
+    fn verifying_partial_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let leaves = INCREASINGLY_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|e| hash_leaf(e))
+            .collect::<Vec<_>>();
+        let level_two_node = hash_node(
+            &hash_node(&leaves[0], &leaves[1]),
+            &hash_node(&leaves[2], &leaves[3]),
+        );
+
+        let proof = get_partial_proof(&mt, 2, 2)
+            .expect("Should have received a valid partial proof for the third element");
+
+        assert!(verify_partial_proof(level_two_node, &proof));
+        assert!(!verify_partial_proof(get_root(&mt).to_string(), &proof));
+        assert!(get_partial_proof(&mt, 2, 4).is_err());
+    }
+
+    #[test]
+    fn proving_elements_at_the_end_of_odd_rows() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+
+        for i in 0..EVEN_MORE_TEST_ELEMENTS.len() {
+            let proof = get_proof(&mt, i)
+                .expect("Should have received a valid proof for any of the original elements");
+
+            assert!(verify_proof(&get_root(&mt), &proof))
+        }
+    }
+
+    #[test]
+    fn displaying_trees() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+
+        let rendered = mt.to_string();
+        let first_line = rendered.lines().next().unwrap_or_default();
+        let leaf_lines = rendered.lines().filter(|line| line.ends_with('"')).count();
+
+        assert!(first_line.starts_with(&get_root(&mt)[..8]));
+        assert_eq!(leaf_lines, mt.leaves.len());
+        assert!(rendered.contains("\"valid\""));
+
+        // the copy of the last node of an odd row is marked rather than rendered again
+        let duplicated_mt = MerkleTreeBuilder::new()
+            .with_odd_handling(OddHandling::DuplicateLast)
+            .build(&EVEN_MORE_TEST_ELEMENTS.map(String::from))
+            .expect("Should have received a valid tree given const test inputs");
+
+        let rendered = duplicated_mt.to_string();
+        let leaf_lines = rendered.lines().filter(|line| line.ends_with('"')).count();
+
+        assert_eq!(leaf_lines, duplicated_mt.leaves.len());
+        assert_eq!(rendered.matches("(duplicate)").count(), 1);
+        assert!(!rendered.contains("\"\""));
+    }
+
+    #[test]
+    fn updating_elements_of_padded_trees() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let new_element = "extra";
+        let expected_root = get_expected_root_hash(vec!["some", "test", "extra", "elements"]);
+
+        let updated_mt = update_element(mt, 2, new_element).expect(
+            "Should have received a valid tree from the implementation given these known inputs",
+        );
+
+        assert_eq!(updated_mt.leaves[2], new_element);
+        assert_eq!(get_root(&updated_mt), expected_root);
+    }
+
+    #[test]
+    fn updating_elements_beyond_the_unpadded_elements() {
+        // elements that are empty strings are elements all the same, and are kept
+        let mt = get_test_tree(vec!["some", "", "", ""]);
+        let updated_mt = update_element(mt, 3, "extra")
+            .expect("Should have received a valid tree given an index among the elements");
+        assert_eq!(updated_mt.leaves(), ["some", "", "", "extra", "", ""]);
+
+        let mt = get_test_tree(vec!["a", "", "b", "c"]);
+        let updated_mt = update_element(mt, 0, "x")
+            .expect("Should have received a valid tree given an index among the elements");
+        assert_eq!(&updated_mt.leaves()[..5], ["x", "a", "", "b", "c"]);
+
+        // only the padding past the elements is beyond them
+        let fixed_mt = create_merkle_tree_with_config(
+            &TEST_ELEMENTS.map(String::from),
+            TreeConfig::default().with_fixed_depth(3),
+        )
+        .expect("Should have received a valid tree given const test inputs");
+        assert!(update_element(fixed_mt, 5, "extra").is_err());
+    }
+
+    #[test]
+    fn previewing_updated_roots() {
+        for (input, index) in [
+            (TEST_ELEMENTS.to_vec(), 1),
+            (TEST_ELEMENTS.to_vec(), 2),
+            (INCREASINGLY_MORE_TEST_ELEMENTS.to_vec(), 5),
+        ] {
+            let mt = get_test_tree(input.to_vec());
+            let root = get_root(&mt);
+
+            let preview =
+                preview_update_root(&mt, index, "new").expect("Should have previewed the update");
+            assert_eq!(get_root(&mt), root);
+
+            let updated_mt = update_element(get_test_tree(input), index, "new")
+                .expect("Should have received a valid updated tree");
+            assert_eq!(get_root(&updated_mt), preview);
+        }
+
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        assert!(preview_update_root(&mt, 4, "new").is_err());
+        let padded_mt = get_test_tree(vec!["some", "", "", ""]);
+        assert_eq!(
+            preview_update_root(&padded_mt, 3, "extra"),
+            Ok(get_test_tree(vec!["some", "", "", "extra", ""]).root_hash)
+        );
+
+        let mt = get_test_tree(vec!["a", "", "b", "c"]);
+        assert_eq!(
+            preview_update_root(&mt, 0, "x"),
+            Ok(get_test_tree(vec!["x", "a", "", "b", "c"]).root_hash)
+        );
+
+        // the gaps of an indexed tree are elements, which keep their place
+        let elements = HashMap::from([
+            (0, "a".to_string()),
+            (3, "b".to_string()),
+            (5, "c".to_string()),
+        ]);
+        let indexed_mt = || {
+            create_merkle_tree_indexed(&elements, 8)
+                .expect("Should have received a valid tree given in-range indices")
+        };
+
+        for (index, expected) in [
+            (1, vec!["a", "x", "", "", "b", "", "c", "", ""]),
+            (6, vec!["a", "", "", "b", "", "c", "x", "", ""]),
+        ] {
+            let preview = preview_update_root(&indexed_mt(), index, "x")
+                .expect("Should have previewed the update");
+            let updated_mt = update_element(indexed_mt(), index, "x")
+                .expect("Should have received a valid updated tree");
+
+            assert_eq!(&updated_mt.leaves()[..9], expected);
+            assert_eq!(updated_mt.root_hash, preview);
+        }
+    }
+
+    #[test]
+    fn diffing_trees() {
+        let old_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let mut elements = INCREASINGLY_MORE_TEST_ELEMENTS.to_vec();
+        elements[5] = "changed";
+        let new_mt = get_test_tree(elements);
+
+        assert_eq!(diff(&old_mt, &new_mt), vec![5]);
+        assert!(diff(&old_mt, &old_mt).is_empty());
+    }
+
+    #[test]
+    fn finding_the_first_divergent_node() {
+        let old_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let mut elements = INCREASINGLY_MORE_TEST_ELEMENTS.to_vec();
+        elements[5] = "changed";
+        let new_mt = get_test_tree(elements);
+
+        assert_eq!(first_divergent_node(&old_mt, &new_mt), Some((0, 5)));
+        assert_eq!(first_divergent_node(&old_mt, &old_mt), None);
+
+        for level in 1..=old_mt.height() {
+            let ancestor = 5 >> level;
+
+            assert_ne!(
+                subtree_root(&old_mt, level, ancestor),
+                subtree_root(&new_mt, level, ancestor)
+            );
+            assert_eq!(
+                subtree_root(&old_mt, level, ancestor ^ 1).ok(),
+                subtree_root(&new_mt, level, ancestor ^ 1).ok()
+            );
+        }
+
+        let taller_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let shorter_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS[..4].to_vec());
+        assert_eq!(first_divergent_node(&taller_mt, &shorter_mt), Some((0, 4)));
+    }
+
+    #[test]
+    fn appending_elements() {
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let extra = ["more", "test", "elements"];
+
+        let appended_mt = append_elements(mt, &extra.map(String::from))
+            .expect("Should have appended the elements");
+        let expected_mt = get_test_tree([TEST_ELEMENTS.as_slice(), &extra].concat());
+
+        assert_eq!(get_root(&appended_mt), get_root(&expected_mt));
+        assert_eq!(appended_mt.leaves(), expected_mt.leaves());
+        assert_eq!(appended_mt.real_leaf_count(), 6);
+    }
+
+    #[test]
+    fn finding_matching_leaves() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        let indices = find_indices_where(&mt, |leaf| leaf.contains('e'));
+        assert_eq!(indices, vec![0, 1, 3, 4, 6]);
+
+        let proof = get_sparse_proof(&mt, &indices)
+            .expect("Should have received a valid proof for the matching elements");
+        assert!(verify_sparse_proof(&get_root(&mt), &proof));
+
+        assert!(find_indices_where(&mt, str::is_empty).is_empty());
+    }
+
+    #[test]
+    fn diffing_trees_of_different_sizes() {
+        let old_mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        let new_mt = get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec());
+
+        assert_eq!(diff(&old_mt, &new_mt), vec![2, 3, 4, 5]);
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn rehashing_trees() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let rehashed_mt =
+            rehash_with(&mt, Blake3Hasher).expect("Should have received a rehashed tree");
+
+        assert_ne!(get_root(&rehashed_mt), get_root(&mt));
+        assert_eq!(rehashed_mt.leaves(), mt.leaves());
+
+        for index in 0..LOTS_MORE_TEST_ELEMENTS.len() {
+            let proof = get_proof(&rehashed_mt, index).expect("Should have received a valid proof");
+
+            assert!(verify_proof(&get_root(&rehashed_mt), &proof));
+            assert!(!verify_proof(&get_root(&mt), &proof));
+        }
+
+        let restored_mt =
+            rehash_with(&rehashed_mt, Sha256Hasher).expect("Should have received a rehashed tree");
+        assert_eq!(get_root(&restored_mt), get_root(&mt));
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    fn generating_keccak_trees() {
+        // computed independently with a reference Keccak-256 implementation
+        const EMPTY_KECCAK: &str =
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+        const TEST_ELEMENTS_KECCAK_ROOT: &str =
+            "bfa13383b0c5a10bf8a1c220ffb2699c998a3cd2c613a8056833b00d06808905";
+
+        let config = TreeConfig::default().with_hasher(Keccak256Hasher);
+        let elements = TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        let mt = create_merkle_tree_with_config(&elements, config.to_owned())
+            .expect("Should have received a valid tree given const test inputs");
+        let proof =
+            get_proof(&mt, 1).expect("Should have received a valid proof for the second element");
+
+        assert_eq!(config.hash_leaf(""), EMPTY_KECCAK);
+        assert_eq!(get_root(&mt), TEST_ELEMENTS_KECCAK_ROOT);
+        assert!(verify_proof(&get_root(&mt), &proof));
+        let sha256_root =
+            RootHash::try_from(get_expected_root_hash(TEST_ELEMENTS.to_vec()).as_str())
+                .expect("Should have accepted a well-formed root hash");
+        assert!(!verify_proof(&sha256_root, &proof));
+    }
+
+    #[test]
+    fn configuring_the_default_hasher() {
+        let elements = TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        let mt = create_merkle_tree_with_config(
+            &elements,
+            TreeConfig::default().with_hasher(Sha256Hasher),
+        )
+        .expect("Should have received a valid tree given const test inputs");
+
+        assert_eq!(
+            get_root(&mt),
+            get_expected_root_hash(TEST_ELEMENTS.to_vec())
+        );
+    }
+
+    #[test]
+    fn extracting_verified_aggregate_proof_elements() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_aggregate_proof(&mt, 2, 6)
+            .expect("Should have received a valid proof for the elements [2,6)");
+        let elements = verify_aggregate_proof_elements(&get_root(&mt), &proof)
+            .expect("Should have verified the proof against the tree it came from");
+
+        assert_eq!(elements, INCREASINGLY_MORE_TEST_ELEMENTS[2..6].to_vec());
+        assert!(verify_aggregate_proof_elements(&get_wrong_root(), &proof).is_err());
+    }
+
+    #[test]
+    fn generating_single_element_trees() {
+        let mt = get_test_tree(vec!["only"]);
+        let expected_root = hash_node(&hash_leaf("only"), &hash_leaf(""));
+
+        let proof =
+            get_proof(&mt, 0).expect("Should have received a valid proof for the only element");
+
+        assert_eq!(mt.leaves, vec!["only", ""]);
+        assert_eq!(get_root(&mt), expected_root);
+        assert_eq!(get_root(&mt), get_expected_root_hash(vec!["only"]));
+        assert!(verify_proof(&get_root(&mt), &proof));
+        let leaf_root = RootHash::try_from(hash_leaf("only").as_str())
+            .expect("Should have accepted a well-formed root hash");
+        assert!(!verify_proof(&leaf_root, &proof));
+    }
+
+    #[test]
+    fn generating_all_proofs() {
+        for input in [
+            INCREASINGLY_MORE_TEST_ELEMENTS.to_vec(),
+            EVEN_MORE_TEST_ELEMENTS.to_vec(),
+        ] {
+            let mt = get_test_tree(input.to_vec());
+
+            let proofs =
+                get_all_proofs(&mt).expect("Should have received a proof for every element");
+
+            // the padding leaf of an odd element count gets no proof, as with `get_proof`
+            assert_eq!(proofs.len(), input.len());
+            for (i, proof) in proofs.iter().enumerate() {
+                let expected = get_proof(&mt, i)
+                    .expect("Should have received a valid proof for any of the original elements");
+
+                assert_eq!(format!("{proof:?}"), format!("{expected:?}"));
+                assert!(verify_proof(&get_root(&mt), proof));
+            }
+        }
+    }
+
+    #[test]
+    fn reconstructing_proof_roots() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof =
+            get_proof(&mt, 3).expect("Should have received a valid proof for the fourth element");
+
+        assert_eq!(get_root(&mt), proof_root(&proof));
+    }
+
+    #[test]
+    fn separating_tree_domains() {
+        let elements = TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let mt_a =
+            create_merkle_tree_with_config(&elements, TreeConfig::default().with_domain("A"))
+                .expect("Should have received a valid tree given const test inputs");
+        let mt_b =
+            create_merkle_tree_with_config(&elements, TreeConfig::default().with_domain("B"))
+                .expect("Should have received a valid tree given const test inputs");
+
+        let proof_a =
+            get_proof(&mt_a, 0).expect("Should have received a valid proof for the first element");
+        let proof_b =
+            get_proof(&mt_b, 0).expect("Should have received a valid proof for the first element");
+
+        assert_ne!(get_root(&mt_a), get_root(&mt_b));
+        assert_ne!(
+            get_root(&mt_a),
+            get_expected_root_hash(TEST_ELEMENTS.to_vec())
+        );
+        assert!(verify_proof(&get_root(&mt_a), &proof_a));
+        assert!(verify_proof(&get_root(&mt_b), &proof_b));
+        assert!(!verify_proof(&get_root(&mt_b), &proof_a));
+        assert!(!verify_proof(&get_root(&mt_a), &proof_b));
+    }
+
+    #[test]
+    fn counting_padding_leaves() {
+        let padded_mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let unpadded_mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+
+        assert_eq!(padded_mt.leaves().len(), 4);
+        assert_eq!(padded_mt.real_leaf_count(), 3);
+        assert!(!padded_mt.is_padding(2));
+        assert!(padded_mt.is_padding(3));
+        assert!(!padded_mt.is_padding(4));
+        assert_eq!(unpadded_mt.real_leaf_count(), 4);
+        assert!((0..4).all(|i| !unpadded_mt.is_padding(i)));
+    }
+
+    #[test]
+    fn proving_padding_leaves() {
         let mt = get_test_tree(TEST_ELEMENTS.to_vec());
 
+        assert!(mt.is_padding(3));
+        assert_eq!(
+            get_proof(&mt, 3),
+            Err("requested index is a padding leaf, not a real element".to_string())
+        );
+
+        let proof = get_proof_allow_padding(&mt, 3)
+            .expect("Should have received a proof for the padding leaf");
+
+        assert!(verify_proof(&get_root(&mt), &proof));
+    }
+
+    #[test]
+    fn predicting_tree_heights() {
+        for count in 1..=16 {
+            let input = (0..count).map(|i| i.to_string()).collect::<Vec<_>>();
+            let mt = create_merkle_tree(&input)
+                .expect("Should have received a valid tree given generated test inputs");
+
+            assert_eq!(required_height(count), mt.height(), "element count {count}");
+        }
+
+        assert_eq!(required_height(0), 0);
+        assert_eq!(required_height(usize::MAX), usize::BITS as usize);
+
+        let oversized_mt = create_merkle_tree_indexed_with_config(
+            &HashMap::new(),
+            usize::MAX,
+            TreeConfig::default().with_max_depth(32),
+        );
+        assert!(oversized_mt.is_err());
+    }
+
+    #[test]
+    fn reading_node_fields() {
+        let node = MerkleNode::from("some".to_string());
+
+        assert_eq!(node.value(), hash_leaf("some"));
+        assert!(node.left().is_none());
+        assert!(node.right().is_none());
+    }
+
+    #[test]
+    fn rejecting_duplicate_elements() {
+        let unique = MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let duplicated = ["some", "more", "test", "more", "elements"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        let unique_mt = create_merkle_tree_unique(&unique)
+            .expect("Should have received a valid tree given unique test inputs");
+        let result = create_merkle_tree_unique(&duplicated);
+
+        assert_eq!(
+            get_root(&unique_mt),
+            get_expected_root_hash(MORE_TEST_ELEMENTS.to_vec())
+        );
+        assert_eq!(result.unwrap_err(), "duplicate element at index 3");
+        assert!(create_merkle_tree(&duplicated).is_ok());
+    }
+
+    #[test]
+    fn updating_elements_in_place() {
+        let mut mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let mut elements = INCREASINGLY_MORE_TEST_ELEMENTS.to_vec();
+        elements[5] = "changed";
+        let rebuilt_mt = get_test_tree(elements);
+
+        update_element_fast(&mut mt, 5, "changed")
+            .expect("Should have updated an element within the bounds of the tree");
+        let proof = get_proof(&mt, 5).expect("Should have received a valid proof for the update");
+
+        assert_eq!(get_root(&mt), get_root(&rebuilt_mt));
+        assert_eq!(mt.leaves, rebuilt_mt.leaves);
+        assert!(verify_proof(&get_root(&mt), &proof));
+        assert!(update_element_fast(&mut mt, 8, "changed").is_err());
+    }
+
+    #[test]
+    fn updating_elements_in_place_of_odd_rows() {
+        let mut mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let mut elements = EVEN_MORE_TEST_ELEMENTS.to_vec();
+        elements.push("padding");
+        let rebuilt_mt = get_test_tree(elements);
+
+        update_element_fast(&mut mt, 5, "padding")
+            .expect("Should have updated an element within the bounds of the tree");
+
+        assert_eq!(get_root(&mt), get_root(&rebuilt_mt));
+        assert_eq!(mt.real_leaf_count(), 6);
+    }
+
+    #[test]
+    fn updating_elements_in_place_with_fewer_hashes() {
+        let elements = (0..1024).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mut fast_mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given generated test inputs");
+        let mut updated_elements = elements.to_owned();
+        updated_elements[300] = "changed".to_string();
+
+        let before_fast = HASH_COUNT.with(|count| count.get());
+        update_element_fast(&mut fast_mt, 300, "changed")
+            .expect("Should have updated an element within the bounds of the tree");
+        let fast_hashes = HASH_COUNT.with(|count| count.get()) - before_fast;
+
+        let before_rebuild = HASH_COUNT.with(|count| count.get());
+        let rebuilt_mt = create_merkle_tree(&updated_elements)
+            .expect("Should have received a valid tree given generated test inputs");
+        let rebuild_hashes = HASH_COUNT.with(|count| count.get()) - before_rebuild;
+
+        assert_eq!(get_root(&fast_mt), get_root(&rebuilt_mt));
+        assert_eq!(fast_hashes, 1 + fast_mt.height());
+        assert_eq!(rebuild_hashes, 1024 + 1023);
+    }
+
+    #[test]
+    fn verifying_proofs_with_height() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let height = required_height(INCREASINGLY_MORE_TEST_ELEMENTS.len());
+
+        let proof =
+            get_proof(&mt, 2).expect("Should have received a valid proof for the third element");
+        let mut short_proof =
+            get_proof(&mt, 2).expect("Should have received a valid proof for the third element");
+        short_proof.siblings.pop();
+        short_proof.directions.pop();
+        let mut long_proof =
+            get_proof(&mt, 2).expect("Should have received a valid proof for the third element");
+        long_proof.siblings.push(hash_leaf(""));
+        long_proof.directions.push(false);
+
+        assert!(verify_proof_with_height(&get_root(&mt), &proof, height));
+        assert!(!verify_proof_with_height(
+            &get_root(&mt),
+            &short_proof,
+            height
+        ));
+        assert!(!verify_proof_with_height(
+            &get_root(&mt),
+            &long_proof,
+            height
+        ));
+    }
+
+    #[test]
+    fn concatenating_node_bytes() {
+        let elements = MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let leaves = elements
+            .iter()
+            .map(|e| hex::decode(hash_leaf(e)).unwrap())
+            .collect::<Vec<_>>();
+        let digest = |left: &[u8], right: &[u8]| Sha256Hasher.digest(&[left, right].concat());
+        let expected_root = hex::encode(digest(
+            &digest(&leaves[0], &leaves[1]),
+            &digest(&leaves[2], &leaves[3]),
+        ));
+
+        let mt =
+            create_merkle_tree_with_config(&elements, TreeConfig::default().with_byte_concat(true))
+                .expect("Should have received a valid tree given const test inputs");
+        let proof =
+            get_proof(&mt, 2).expect("Should have received a valid proof for the third element");
+
+        assert_eq!(get_root(&mt), expected_root);
+        assert_ne!(
+            get_root(&mt),
+            get_expected_root_hash(MORE_TEST_ELEMENTS.to_vec())
+        );
+        assert!(verify_proof(&get_root(&mt), &proof));
+    }
+
+    #[test]
+    fn concatenating_node_bytes_of_odd_rows() {
+        let mt = create_merkle_tree_with_config(
+            &EVEN_MORE_TEST_ELEMENTS
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>(),
+            TreeConfig::default().with_byte_concat(true),
+        )
+        .expect("Should have received a valid tree given const test inputs");
+
+        for proof in get_all_proofs(&mt).expect("Should have received a proof for every leaf") {
+            assert!(verify_proof(&get_root(&mt), &proof));
+        }
+    }
+
+    #[test]
+    fn serializing_trees() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        let loaded_mt = MerkleTree::from_bytes(&mt.to_bytes())
+            .expect("Should have loaded the tree that was just serialized");
+
+        assert_eq!(get_root(&loaded_mt), get_root(&mt));
+        assert_eq!(loaded_mt.leaves, mt.leaves);
+        assert_eq!(loaded_mt.real_leaf_count(), mt.real_leaf_count());
+    }
+
+    #[test]
+    fn serializing_corrupted_trees() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let data = mt.to_bytes();
+
+        // the first byte of the first leaf, just after the counts and its length prefix
+        let mut flipped_leaf = data.to_owned();
+        flipped_leaf[24] ^= 0x01;
+        let mut flipped_root = data.to_owned();
+        let last = flipped_root.len() - 1;
+        flipped_root[last] ^= 0x01;
+
+        assert!(MerkleTree::from_bytes(&flipped_leaf).is_err());
+        assert!(MerkleTree::from_bytes(&flipped_root).is_err());
+        assert!(MerkleTree::from_bytes(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn verifying_aggregate_proofs_checked() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_aggregate_proof(&mt, 2, 6)
+            .expect("Should have received a valid proof for the elements [2,6)");
+
+        assert_eq!(
+            verify_aggregate_proof_checked(&get_root(&mt), &proof),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_aggregate_proof_checked(&get_wrong_root(), &proof),
+            Ok(VERIFY_PROOF_FAILED)
+        );
+    }
+
+    #[test]
+    fn verifying_malformed_aggregate_proofs_checked() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let mut odd_proof = get_aggregate_proof(&mt, 2, 6)
+            .expect("Should have received a valid proof for the elements [2,6)");
+        odd_proof.siblings.pop();
+        odd_proof.directions.pop();
+        let mut mismatched_proof = get_aggregate_proof(&mt, 2, 6)
+            .expect("Should have received a valid proof for the elements [2,6)");
+        mismatched_proof.directions.pop();
+
+        assert_eq!(
+            verify_aggregate_proof_checked(&get_root(&mt), &odd_proof),
+            Err(MerkleError::UnpairedAggregateSiblings(3))
+        );
+        assert_eq!(
+            verify_aggregate_proof_checked(&get_root(&mt), &mismatched_proof),
+            Err(MerkleError::MismatchedProofLengths {
+                siblings: 4,
+                directions: 3
+            })
+        );
+    }
+
+    #[test]
+    fn building_trees_with_default_options() {
+        let elements = LOTS_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        let built_mt = MerkleTreeBuilder::new()
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+        let legacy_mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        assert_eq!(get_root(&built_mt), get_root(&legacy_mt));
+        assert_eq!(built_mt.leaves, legacy_mt.leaves);
+    }
+
+    #[test]
+    fn building_trees_with_options() {
+        let elements = EVEN_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let mut padded_elements = elements.to_owned();
+        padded_elements.push("PAD".to_string());
+
+        let padded_mt = MerkleTreeBuilder::new()
+            .with_padding("PAD")
+            .with_domain("registry")
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+        let expected_mt = create_merkle_tree_with_config(
+            &padded_elements,
+            TreeConfig::default().with_domain("registry"),
+        )
+        .expect("Should have received a valid tree given const test inputs");
+
+        let fixed_mt = MerkleTreeBuilder::new()
+            .with_byte_concat(true)
+            .fixed_depth(4)
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        assert_eq!(get_root(&padded_mt), get_root(&expected_mt));
+        assert_eq!(fixed_mt.leaves.len(), 16);
+        assert_eq!(fixed_mt.height(), 4);
+        for proof in get_all_proofs(&fixed_mt).expect("Should have received a proof for every leaf")
+        {
+            assert!(verify_proof(&get_root(&fixed_mt), &proof));
+        }
+        assert!(MerkleTreeBuilder::new()
+            .fixed_depth(2)
+            .build(&elements)
+            .is_err());
+    }
+
+    #[test]
+    fn limiting_leaf_counts() {
+        let elements = EVEN_MORE_TEST_ELEMENTS.map(String::from);
+
+        let result = MerkleTreeBuilder::new().max_leaves(4).build(&elements);
+        assert_eq!(
+            result.expect_err("Should have rejected 5 elements"),
+            "leaf count exceeds configured maximum"
+        );
+
+        let limited_mt = MerkleTreeBuilder::new()
+            .max_leaves(4)
+            .build(&elements[..4])
+            .expect("Should have received a valid tree within the limit");
+        assert_eq!(
+            get_root(&limited_mt),
+            get_root(&get_test_tree(EVEN_MORE_TEST_ELEMENTS[..4].to_vec()))
+        );
+    }
+
+    #[test]
+    fn limiting_tree_depths() {
+        let elements = (0..9).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        let result = MerkleTreeBuilder::new().max_depth(3).build(&elements);
+        assert_eq!(
+            result.expect_err("Should have rejected a tree of height 4"),
+            "tree depth exceeds maximum"
+        );
+
+        let limited_mt = MerkleTreeBuilder::new()
+            .max_depth(3)
+            .build(&elements[..8])
+            .expect("Should have received a valid tree of height 3");
+        assert_eq!(limited_mt.height(), 3);
+
+        assert!(MerkleTreeBuilder::new()
+            .max_depth(3)
+            .fixed_depth(4)
+            .build(&elements[..2])
+            .is_err());
+    }
+
+    #[test]
+    fn getting_siblings_at_each_level() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        for i in 0..mt.leaves.len() {
+            let proof = get_proof_allow_padding(&mt, i)
+                .expect("Should have received a valid proof for any of the original elements");
+
+            for level in 0..mt.height() {
+                let (sibling, side) = sibling_at_level(&mt, i, level)
+                    .expect("Should have received a sibling for every level below the root");
+
+                assert_eq!(sibling, proof.siblings[level]);
+                assert_eq!(side == Side::Left, proof.directions[level]);
+            }
+        }
+
+        assert!(sibling_at_level(&mt, 0, mt.height()).is_err());
+        assert!(sibling_at_level(&mt, mt.leaves.len(), 0).is_err());
+    }
+
+    #[test]
+    fn duplicating_the_last_node_of_odd_rows() {
+        let elements = TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let leaves = TEST_ELEMENTS
+            .iter()
+            .map(|e| hash_leaf(e))
+            .collect::<Vec<_>>();
+        let expected_root = hash_node(
+            &hash_node(&leaves[0], &leaves[1]),
+            &hash_node(&leaves[2], &leaves[2]),
+        );
+
+        let padded_mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+        let duplicated_mt = MerkleTreeBuilder::new()
+            .with_odd_handling(OddHandling::DuplicateLast)
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        assert_eq!(get_root(&duplicated_mt), expected_root);
+        assert_ne!(get_root(&duplicated_mt), get_root(&padded_mt));
+        assert!(duplicated_mt.is_padding(3));
+    }
+
+    #[test]
+    fn proving_elements_with_duplicated_last_nodes() {
+        let elements = EVEN_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let mut mt = MerkleTreeBuilder::new()
+            .with_odd_handling(OddHandling::DuplicateLast)
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        for proof in get_all_proofs(&mt).expect("Should have received a proof for every leaf") {
+            assert!(verify_proof(&get_root(&mt), &proof));
+        }
+
+        let mut updated_elements = elements.to_owned();
+        updated_elements[4] = "changed".to_string();
+        let rebuilt_mt = MerkleTreeBuilder::new()
+            .with_odd_handling(OddHandling::DuplicateLast)
+            .build(&updated_elements)
+            .expect("Should have received a valid tree given const test inputs");
+        update_element_fast(&mut mt, 4, "changed")
+            .expect("Should have updated an element within the bounds of the tree");
+        assert_eq!(get_root(&mt), get_root(&rebuilt_mt));
+    }
+
+    #[test]
+    fn validating_tree_integrity() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        assert!(validate_integrity(&mt));
+
+        let mut tampered_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        tampered_mt.root_hash = get_root(&get_test_tree(MORE_TEST_ELEMENTS.to_vec())).to_string();
+        assert!(!validate_integrity(&tampered_mt));
+
+        let mut tampered_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        tampered_mt.leaves[2] = "tampered".to_string();
+        assert!(!validate_integrity(&tampered_mt));
+    }
+
+    #[test]
+    fn serializing_proofs() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 2).expect("Should have received a valid proof for index 2");
+
+        let data = proof.to_bytes();
+        assert_eq!(data[0], 4);
+
+        let decoded_proof =
+            MerkleProof::from_bytes(&data).expect("Should have decoded a freshly encoded proof");
+        assert_eq!(decoded_proof.element, proof.element);
+        assert_eq!(decoded_proof.siblings, proof.siblings);
+        assert_eq!(decoded_proof.directions, proof.directions);
+        assert!(verify_proof(&get_root(&mt), &decoded_proof));
+
+        let aggregate_proof = get_aggregate_proof(&mt, 1, 3)
+            .expect("Should have received a valid proof for the elements [1,3)");
+        let decoded_aggregate_proof = MerkleAggregateProof::from_bytes(&aggregate_proof.to_bytes())
+            .expect("Should have decoded a freshly encoded aggregate proof");
+        assert_eq!(decoded_aggregate_proof.elements, aggregate_proof.elements);
+        assert_eq!(decoded_aggregate_proof.siblings, aggregate_proof.siblings);
+        assert_eq!(
+            decoded_aggregate_proof.directions,
+            aggregate_proof.directions
+        );
+        assert_eq!(decoded_aggregate_proof, aggregate_proof);
+    }
+
+    #[test]
+    fn rebuilding_proofs_from_cached_paths() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 4).expect("Should have received a valid proof");
+
+        let path = proof
+            .siblings
+            .iter()
+            .cloned()
+            .zip(proof.directions.iter().copied())
+            .collect::<Vec<_>>();
+        let rebuilt_proof = MerkleProof::from_path(proof.element().to_string(), path);
+
+        assert_eq!(rebuilt_proof, proof);
+        assert!(verify_proof(&get_root(&mt), &rebuilt_proof));
+        assert!(!verify_proof(&get_wrong_root(), &rebuilt_proof));
+    }
+
+    #[test]
+    fn converting_proofs_to_json() {
+        let mt = get_test_tree(vec![
+            "some",
+            "\"quoted\"\n\\",
+            "caf\u{e9} \u{1f600}",
+            "\u{1}",
+        ]);
+
+        for index in 0..4 {
+            let proof = get_proof(&mt, index).expect("Should have received a valid proof");
+            let json = proof.to_json();
+
+            let decoded_proof =
+                MerkleProof::from_json(&json).expect("Should have parsed freshly rendered JSON");
+            assert_eq!(decoded_proof, proof);
+        }
+
+        let proof = get_proof(&mt, 0).expect("Should have received a valid proof");
+        assert_eq!(
+            proof.to_json(),
+            format!(
+                "{{\"element\":\"some\",\"siblings\":[\"{}\",\"{}\"],\"directions\":[false,false]}}",
+                proof.siblings[0], proof.siblings[1]
+            )
+        );
+
+        let spaced_json = format!(
+            " {{ \"directions\" : [ false , false ] ,\n \"element\" : \"some\" , \"siblings\" : [ \"{}\" , \"{}\" ] }} ",
+            proof.siblings[0], proof.siblings[1]
+        );
+        assert_eq!(
+            MerkleProof::from_json(&spaced_json).expect("Should have parsed spaced out JSON"),
+            proof
+        );
+
+        let weighted_mt = create_weighted_merkle_tree(&[("some".to_string(), 7)])
+            .expect("Should have received a valid weighted tree");
+        let weighted_proof =
+            get_proof(&weighted_mt, 0).expect("Should have received a valid proof");
+        assert!(weighted_proof.to_json().ends_with(",\"weight\":7}"));
+        assert_eq!(
+            MerkleProof::from_json(&weighted_proof.to_json())
+                .expect("Should have parsed freshly rendered JSON"),
+            weighted_proof
+        );
+
+        let json = proof.to_json();
+        for malformed in [
+            "",
+            "[]",
+            &json[..json.len() - 1],
+            &format!("{json}{{}}"),
+            "{\"element\":\"some\",\"siblings\":[]}",
+            "{\"element\":\"some\",\"siblings\":[],\"directions\":[maybe]}",
+            "{\"element\":\"some\",\"element\":\"more\",\"siblings\":[],\"directions\":[]}",
+            "{\"element\":\"\\x\",\"siblings\":[],\"directions\":[]}",
+        ] {
+            assert!(MerkleProof::from_json(malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn rejecting_unknown_proof_versions() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 2).expect("Should have received a valid proof for index 2");
+
+        let mut data = proof.to_bytes();
+        data[0] += 1;
+
+        let result = MerkleProof::from_bytes(&data);
+        assert!(result
+            .expect_err("Should have rejected a proof with a newer version")
+            .contains("unsupported proof version"));
+
+        let aggregate_proof = get_aggregate_proof(&mt, 1, 3)
+            .expect("Should have received a valid proof for the elements [1,3)");
+        let mut data = aggregate_proof.to_bytes();
+        data[0] += 1;
+
+        assert!(MerkleAggregateProof::from_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn verifying_aggregate_proofs_of_the_last_elements() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_aggregate_proof(&mt, 6, 8)
+            .expect("Should have received a valid proof for the elements [6,8)");
+        assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+
+        let proof = get_aggregate_proof(&mt, 0, 8)
+            .expect("Should have received a valid proof for all the elements");
+        assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        for (start, end) in [(0, 1), (1, 4), (3, 6), (4, 5)] {
+            let proof = get_aggregate_proof(&mt, start, end)
+                .expect("Should have received a valid proof for a range within the leaves");
+            assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+        }
+    }
+
+    #[test]
+    fn constructing_root_hashes() {
+        let mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        let root = RootHash::try_from(get_root(&mt).as_str())
+            .expect("Should have accepted the root of a tree");
+        assert_eq!(root, get_root(&mt));
+
+        let uppercase_root = get_root(&mt).to_uppercase();
+        assert!(RootHash::try_from(uppercase_root.as_str()).is_err());
+
+        assert!(RootHash::try_from(&get_root(&mt)[..63]).is_err());
+        assert!(RootHash::try_from(INVALID_HASH).is_err());
+    }
+
+    #[test]
+    fn verifying_sparse_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_sparse_proof(&mt, &[7, 1, 4, 1])
+            .expect("Should have received a valid proof for the elements {1,4,7}");
+
+        assert_eq!(proof.indices, vec![1, 4, 7]);
+        assert_eq!(proof.elements, vec!["more", "elements", "again"]);
+        assert!(verify_sparse_proof(&get_root(&mt), &proof));
+        assert_eq!(
+            verify_sparse_proof(&get_wrong_root(), &proof),
+            VERIFY_PROOF_FAILED
+        );
+
+        let clustered_proof = get_sparse_proof(&mt, &[0, 1, 2, 3])
+            .expect("Should have received a valid proof for the elements {0,1,2,3}");
+        assert_eq!(clustered_proof.siblings.len(), 1);
+        assert!(verify_sparse_proof(&get_root(&mt), &clustered_proof));
+
+        let odd_mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let odd_proof = get_sparse_proof(&odd_mt, &[2, 6])
+            .expect("Should have received a valid proof for the elements {2,6}");
+        assert!(verify_sparse_proof(&get_root(&odd_mt), &odd_proof));
+
+        assert!(get_sparse_proof(&mt, &[]).is_err());
+        assert!(get_sparse_proof(&mt, &[1, 8]).is_err());
+    }
+
+    #[test]
+    fn reading_proven_elements() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+        assert_eq!(proof.element(), INCREASINGLY_MORE_TEST_ELEMENTS[3]);
+
+        let aggregate_proof = get_aggregate_proof(&mt, 2, 6)
+            .expect("Should have received a valid proof for the elements [2,6)");
+        assert_eq!(
+            aggregate_proof.elements(),
+            &INCREASINGLY_MORE_TEST_ELEMENTS[2..6]
+        );
+    }
+
+    #[test]
+    fn encoding_hashes_as_base64() {
+        let elements = EVEN_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let config = TreeConfig::default().with_encoding(Encoding::Base64);
+
+        let mt = create_merkle_tree_with_config(&elements, config.to_owned())
+            .expect("Should have received a valid tree given const test inputs");
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+
+        let root = get_root(&mt);
+        assert_eq!(
+            RootHash::try_from_encoded(&root, Encoding::Base64),
+            Ok(root.to_owned())
+        );
+        assert!(RootHash::try_from(root.as_str()).is_err());
+        assert_eq!(
+            config.hash_leaf("some"),
+            "prRt0NGuXobLyPN+dc7rZ2AjDByk/7ywyXuW3X2cRks="
+        );
+        assert!(verify_proof(&root, &proof));
+
+        let decoded_proof = MerkleProof::from_bytes_with_config(&proof.to_bytes(), config)
+            .expect("Should have decoded a freshly encoded proof");
+        assert!(verify_proof(&root, &decoded_proof));
+
+        let hex_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        assert!(!verify_proof(&get_root(&hex_mt), &proof));
+    }
+
+    #[test]
+    fn truncating_trees() {
+        let mt = get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec());
+        let expected_mt = get_test_tree(YET_MORE_TEST_ELEMENTS[..4].to_vec());
+
+        let truncated_mt = truncate(mt, 4).expect("Should have truncated to fewer elements");
+
+        assert_eq!(get_root(&truncated_mt), get_root(&expected_mt));
+        assert_eq!(truncated_mt.leaves(), expected_mt.leaves());
+        assert!(truncate(get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec()), 7).is_err());
+        assert!(truncate(get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec()), 0).is_err());
+    }
+
+    #[test]
+    fn verifying_prehashed_proofs() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        for index in 0..mt.leaves().len() {
+            let proof =
+                get_proof_allow_padding(&mt, index).expect("Should have received a valid proof");
+            let leaf_hash = hash_leaf(proof.element());
+
+            assert_eq!(
+                verify_proof_prehashed(
+                    &get_root(&mt),
+                    &leaf_hash,
+                    &proof.siblings,
+                    &proof.directions
+                ),
+                verify_proof(&get_root(&mt), &proof)
+            );
+            assert!(verify_proof_prehashed(
+                &get_root(&mt),
+                &leaf_hash,
+                &proof.siblings,
+                &proof.directions
+            ));
+        }
+
+        let proof = get_proof(&mt, 2).expect("Should have received a valid proof for index 2");
+        assert!(!verify_proof_prehashed(
+            &get_root(&mt),
+            proof.element(),
+            &proof.siblings,
+            &proof.directions
+        ));
+    }
+
+    #[test]
+    fn minimizing_aggregate_proofs() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_aggregate_proof(&mt, 5, 7)
+            .expect("Should have received a valid proof for the elements [5,7)");
+        let sibling_count = proof.siblings.len();
+
+        let minimized_proof = proof.minimize();
+
+        assert!(minimized_proof.siblings.len() < sibling_count);
+        assert!(verify_aggregate_proof(&get_root(&mt), &minimized_proof));
+        assert_eq!(
+            verify_aggregate_proof_checked(&get_root(&mt), &minimized_proof),
+            Ok(true)
+        );
+        assert!(!verify_aggregate_proof(&get_wrong_root(), &minimized_proof));
+
+        let decoded_proof = MerkleAggregateProof::from_bytes(&minimized_proof.to_bytes())
+            .expect("Should have decoded a freshly encoded aggregate proof");
+        assert!(verify_aggregate_proof(&get_root(&mt), &decoded_proof));
+    }
+
+    #[test]
+    fn listing_level_hashes() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let leaf_hashes = mt.leaves().iter().map(|e| hash_leaf(e)).collect::<Vec<_>>();
+
+        assert_eq!(
+            level_hashes(&mt, 0).expect("Should have received the leaf level"),
+            leaf_hashes
+        );
+        assert_eq!(
+            level_hashes(&mt, 1).expect("Should have received the level above the leaves"),
+            vec![
+                hash_node(&leaf_hashes[0], &leaf_hashes[1]),
+                hash_node(&leaf_hashes[2], &leaf_hashes[3]),
+                hash_node(&leaf_hashes[4], &leaf_hashes[5]),
+                hash_node(&leaf_hashes[6], &leaf_hashes[7]),
+            ]
+        );
+        assert_eq!(
+            level_hashes(&mt, mt.height()).expect("Should have received the root level"),
+            vec![get_root(&mt).to_string()]
+        );
+        assert!(level_hashes(&mt, mt.height() + 1).is_err());
+    }
+
+    #[test]
+    fn listing_ancestor_paths() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let path = ancestor_path(&mt, 5).expect("Should have received the path of a leaf");
+
+        assert_eq!(path.len(), mt.height() + 1);
+        assert_eq!(path[0], hash_leaf(LOTS_MORE_TEST_ELEMENTS[5]));
+        assert_eq!(path[path.len() - 1], get_root(&mt).to_string());
+        assert_eq!(
+            path,
+            verify_proof_trace(
+                &get_root(&mt),
+                &get_proof(&mt, 5).expect("Should have received a valid proof")
+            )
+            .1
+        );
+        assert!(ancestor_path(&mt, mt.leaves().len()).is_err());
+    }
+
+    #[test]
+    fn getting_subtree_roots() {
+        let mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+
+        assert_eq!(
+            subtree_root(&mt, mt.height(), 0).expect("Should have received the root"),
+            get_root(&mt).to_string()
+        );
+        assert_eq!(
+            subtree_root(&mt, 0, 5).expect("Should have received a leaf digest"),
+            hash_leaf(LOTS_MORE_TEST_ELEMENTS[5])
+        );
+        assert_eq!(
+            subtree_root(&mt, 1, 1).expect("Should have received an internal node"),
+            hash_node(
+                &hash_leaf(LOTS_MORE_TEST_ELEMENTS[2]),
+                &hash_leaf(LOTS_MORE_TEST_ELEMENTS[3])
+            )
+        );
+        assert!(subtree_root(&mt, 1, 4).is_err());
+        assert!(subtree_root(&mt, mt.height() + 1, 0).is_err());
+    }
+
+    #[test]
+    fn deduplicating_proofs() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+        let same_proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+        let other_proof =
+            get_proof(&mt, 4).expect("Should have received a valid proof for index 4");
+        let decoded_proof = MerkleProof::from_bytes(&proof.to_bytes())
+            .expect("Should have decoded a freshly encoded proof");
+
+        assert_eq!(proof, same_proof);
+        assert_eq!(proof, decoded_proof);
+        assert_ne!(proof, other_proof);
+
+        let proofs = HashSet::from([proof, same_proof, decoded_proof]);
+        assert_eq!(proofs.len(), 1);
+
+        let aggregate_proof = get_aggregate_proof(&mt, 1, 3)
+            .expect("Should have received a valid proof for the elements [1,3)");
+        let same_aggregate_proof = get_aggregate_proof(&mt, 1, 3)
+            .expect("Should have received a valid proof for the elements [1,3)");
+        let aggregate_proofs = HashSet::from([aggregate_proof, same_aggregate_proof]);
+        assert_eq!(aggregate_proofs.len(), 1);
+
+        let domain_proof = get_proof(
+            &create_merkle_tree_with_config(
+                &mt.leaves()[..5],
+                TreeConfig::default().with_domain("other"),
+            )
+            .expect("Should have received a valid tree given const test inputs"),
+            3,
+        )
+        .expect("Should have received a valid proof for index 3");
+        assert_ne!(
+            domain_proof,
+            get_proof(&mt, 3).expect("Should have received a valid proof for index 3")
+        );
+    }
+
+    #[test]
+    fn rejecting_overlong_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_proof(&mt, 5).expect("Should have received a valid proof for index 5");
+        assert_eq!(verify_proof_checked(&get_root(&mt), &proof), Ok(true));
+        assert_eq!(verify_proof_checked(&get_wrong_root(), &proof), Ok(false));
+        assert_eq!(
+            verify_proof_checked_with_max_len(&get_root(&mt), &proof, 2),
+            Err(MerkleError::ProofTooLong { length: 3, max: 2 })
+        );
+
+        let mut long_proof =
+            get_proof(&mt, 5).expect("Should have received a valid proof for index 5");
+        long_proof.siblings = vec![hash_leaf(""); 1000];
+        long_proof.directions = vec![false; 1000];
+
+        let before = HASH_COUNT.with(|count| count.get());
+        let result = verify_proof_checked(&get_root(&mt), &long_proof);
+        let hashes = HASH_COUNT.with(|count| count.get()) - before;
+
+        let error = result.expect_err("Should have rejected a proof with 1000 siblings");
+        assert_eq!(
+            error,
+            MerkleError::ProofTooLong {
+                length: 1000,
+                max: DEFAULT_MAX_PROOF_LEN
+            }
+        );
+        assert!(error.to_string().starts_with("proof too long"));
+        assert_eq!(hashes, 0);
+    }
+
+    #[test]
+    fn building_trees_with_proofs() {
+        for input in [
+            TEST_ELEMENTS.to_vec(),
+            EVEN_MORE_TEST_ELEMENTS.to_vec(),
+            INCREASINGLY_MORE_TEST_ELEMENTS.to_vec(),
+        ] {
+            let elements = input.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            let (mt, proofs) =
+                build_with_proofs(&elements).expect("Should have built a tree with its proofs");
+
+            assert_eq!(get_root(&mt), get_root(&get_test_tree(input)));
+            assert_eq!(proofs.len(), elements.len());
+
+            for (index, proof) in proofs.iter().enumerate() {
+                assert!(verify_proof(&get_root(&mt), proof));
+                assert_eq!(
+                    *proof,
+                    get_proof(&mt, index).expect("Should have received a valid proof")
+                );
+            }
+        }
+
+        assert!(build_with_proofs(&[]).is_err());
+    }
+
+    #[test]
+    fn estimating_proof_sizes() {
+        let elements = INCREASINGLY_MORE_TEST_ELEMENTS.map(String::from).to_vec();
+        let weighted_elements = elements
+            .iter()
+            .map(|element| (element.to_owned(), 1))
+            .collect::<Vec<_>>();
+
+        for mt in [
+            get_test_tree(TEST_ELEMENTS.to_vec()),
+            get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec()),
+            create_merkle_tree_with_config(
+                &elements,
+                TreeConfig::default().with_encoding(Encoding::Base64),
+            )
+            .expect("Should have received a valid tree given const test inputs"),
+            create_weighted_merkle_tree(&weighted_elements)
+                .expect("Should have received a valid tree given const test inputs"),
+        ] {
+            let estimate = estimated_proof_bytes(&mt);
+
+            for index in 0..mt.element_count {
+                let proof = get_proof(&mt, index).expect("Should have received a valid proof");
+                assert_eq!(proof.to_bytes().len(), estimate + proof.element().len());
+            }
+        }
+
+        // the last leaf of five is paired with an empty node on the row above the leaves
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let estimate = estimated_proof_bytes(&mt);
+
+        for index in 0..mt.element_count {
+            let proof = get_proof(&mt, index).expect("Should have received a valid proof");
+            assert!(proof.to_bytes().len() <= estimate + proof.element().len());
+        }
+
+        let proof = get_proof(&mt, 4).expect("Should have received a valid proof for index 4");
+        assert!(proof.to_bytes().len() < estimate + proof.element().len());
+    }
+
+    #[test]
+    fn filtering_proofs_by_root_prefix() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt);
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+
+        assert!(verify_proof_root_prefix(&root[..8], &proof));
+        assert!(verify_proof_root_prefix(&root, &proof));
+        assert!(!verify_proof_root_prefix(&WRONG_ROOT_HASH[..8], &proof));
+        assert!(!verify_proof_root_prefix("", &proof));
+    }
+
+    #[test]
+    fn reconstructing_trees_from_proofs() {
+        let elements = LOTS_MORE_TEST_ELEMENTS.map(String::from).to_vec();
+        let (mt, mut proofs) =
+            build_with_proofs(&elements).expect("Should have built a tree with its proofs");
+
+        let reconstructed_mt =
+            reconstruct_from_proofs(&proofs).expect("Should have reconstructed the tree");
+        assert_eq!(get_root(&reconstructed_mt), get_root(&mt));
+        assert_eq!(reconstructed_mt.leaves(), mt.leaves());
+
+        proofs.reverse();
+        let reconstructed_mt =
+            reconstruct_from_proofs(&proofs).expect("Should have reconstructed the tree");
+        assert_eq!(get_root(&reconstructed_mt), get_root(&mt));
+
+        assert!(reconstruct_from_proofs(&[]).is_err());
+        assert!(reconstruct_from_proofs(&proofs[1..]).is_err());
+
+        let mut tampered_proofs = proofs;
+        tampered_proofs[0] =
+            get_proof(&mt, 1).expect("Should have received a valid proof for index 1");
+        assert!(reconstruct_from_proofs(&tampered_proofs).is_err());
+
+        tampered_proofs[0] =
+            get_proof(&mt, 6).expect("Should have received a valid proof for index 6");
+        tampered_proofs[0].element = "tampered".to_string();
+        assert!(reconstruct_from_proofs(&tampered_proofs).is_err());
+
+        tampered_proofs[0] = MerkleProof::from_path(
+            "tampered".to_string(),
+            vec![(get_wrong_root().to_string(), true); 70],
+        );
+        assert!(reconstruct_from_proofs(&tampered_proofs).is_err());
+    }
+
+    #[test]
+    fn verifying_proofs_against_intermediate_checkpoints() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt);
+        let proof = get_proof(&mt, 5).expect("Should have received a valid proof for index 5");
+        let level_1 = mt.levels[1][2].to_owned();
+
+        assert!(verify_proof_with_expected_intermediate(&root, &proof, &[]));
+        assert!(verify_proof_with_expected_intermediate(
+            &root,
+            &proof,
+            &[(1, level_1.to_owned())]
+        ));
+        assert!(verify_proof_with_expected_intermediate(
+            &root,
+            &proof,
+            &[(0, mt.levels[0][5].to_owned()), (3, root.to_string())]
+        ));
+        assert!(!verify_proof_with_expected_intermediate(
+            &get_wrong_root(),
+            &proof,
+            &[(1, level_1)]
+        ));
+
+        // a wrong checkpoint fails as soon as its level is reached, without climbing further
+        let before = HASH_COUNT.with(|count| count.get());
+        let result = verify_proof_with_expected_intermediate(
+            &root,
+            &proof,
+            &[(1, mt.levels[1][1].to_owned())],
+        );
+        let hashes = HASH_COUNT.with(|count| count.get()) - before;
+        assert!(!result);
+        assert_eq!(hashes, 2);
+
+        assert!(!verify_proof_with_expected_intermediate(
+            &root,
+            &proof,
+            &[(4, root.to_string())]
+        ));
+    }
+
+    #[test]
+    fn splitting_trees_under_the_root() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let (left_mt, right_mt) = split_at(&mt, 4).expect("Should have split the tree at leaf 4");
+
+        assert_eq!(
+            combine_roots(&left_mt, &right_mt).expect("Should have combined the roots"),
+            get_root(&mt)
+        );
+        assert_eq!(
+            get_root(&left_mt),
+            get_root(&get_test_tree(
+                INCREASINGLY_MORE_TEST_ELEMENTS[..4].to_vec()
+            ))
+        );
+        assert_eq!(
+            get_root(&right_mt),
+            get_root(&get_test_tree(
+                INCREASINGLY_MORE_TEST_ELEMENTS[4..].to_vec()
+            ))
+        );
+
+        assert!(validate_integrity(&left_mt));
+        assert!(validate_integrity(&right_mt));
+
+        let proof = get_proof(&right_mt, 1).expect("Should have received a valid proof");
+        assert_eq!(proof.element(), INCREASINGLY_MORE_TEST_ELEMENTS[5]);
+        assert!(verify_proof(&get_root(&right_mt), &proof));
+
+        // an odd element count padded up to a power of two splits like any other
+        let odd_mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+        let (left_mt, right_mt) = split_at(&odd_mt, 4).expect("Should have split the tree");
+        assert_eq!(right_mt.leaves().len(), 4);
+        assert!(validate_integrity(&left_mt));
+        assert!(validate_integrity(&right_mt));
+        assert_eq!(
+            combine_roots(&left_mt, &right_mt).expect("Should have combined the roots"),
+            get_root(&odd_mt)
+        );
+
+        // the right half of ten leaves would only hold together paired with empty nodes
+        let ten_elements = (0..10).map(|i| i.to_string()).collect::<Vec<_>>();
+        let ten_mt = create_merkle_tree(&ten_elements)
+            .expect("Should have received a valid tree given a generated input");
+        assert!(split_at(&ten_mt, 8).is_err());
+
+        assert!(split_at(&mt, 2).is_err());
+        assert!(split_at(&mt, 5).is_err());
+    }
+
+    #[test]
+    fn grouping_aggregate_siblings_by_level() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        // [2, 6) starts on a left child and ends on a right one, so the leaves need no
+        // siblings; its nodes [1, 3) of level 1 need one on either side, and [0, 2) of level 2
+        // span the whole row
+        let proof = get_aggregate_proof(&mt, 2, 6).expect("Should have received a valid proof");
+        assert_eq!(
+            proof.siblings_by_level(),
+            vec![
+                (None, None),
+                (
+                    Some(mt.levels[1][0].to_owned()),
+                    Some(mt.levels[1][3].to_owned())
+                ),
+            ]
+        );
+        assert_eq!(proof.minimize().siblings_by_level().len(), 2);
+
+        let proof = get_aggregate_proof(&mt, 3, 5).expect("Should have received a valid proof");
+        assert_eq!(
+            proof.siblings_by_level(),
+            vec![
+                (
+                    Some(mt.levels[0][2].to_owned()),
+                    Some(mt.levels[0][5].to_owned())
+                ),
+                (
+                    Some(mt.levels[1][0].to_owned()),
+                    Some(mt.levels[1][3].to_owned())
+                ),
+            ]
+        );
+
+        // once both boundaries meet, each level has the one sibling of the path to the root
+        let proof = get_aggregate_proof(&mt, 0, 2).expect("Should have received a valid proof");
+        assert_eq!(
+            proof.siblings_by_level(),
+            vec![
+                (None, None),
+                (None, Some(mt.levels[1][1].to_owned())),
+                (None, Some(mt.levels[2][1].to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn creating_trees_from_indexed_elements() {
+        let elements = HashMap::from([
+            (0, "some".to_string()),
+            (3, "test".to_string()),
+            (5, "elements".to_string()),
+        ]);
+
+        let mt = create_merkle_tree_indexed(&elements, 8)
+            .expect("Should have received a valid tree given in-range indices");
+        assert_eq!(
+            mt.leaves(),
+            ["some", "", "", "test", "", "elements", "", ""]
+        );
+        assert_eq!(
+            get_root(&mt),
+            get_root(&get_test_tree(vec![
+                "some", "", "", "test", "", "elements", "", ""
+            ]))
+        );
+
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+        assert_eq!(proof.element(), "test");
+        assert!(verify_proof(&get_root(&mt), &proof));
+
+        let gap_proof = get_proof(&mt, 4).expect("Should have received a valid proof for a gap");
+        assert!(verify_proof(&get_root(&mt), &gap_proof));
+
+        assert!(create_merkle_tree_indexed(&elements, 5).is_err());
+        assert!(create_merkle_tree_indexed(&HashMap::new(), 0).is_err());
+
+        let limited_mt = create_merkle_tree_indexed_with_config(
+            &elements,
+            usize::MAX,
+            TreeConfig::default().with_max_leaves(8),
+        );
+        assert!(limited_mt.is_err());
+    }
+
+    #[test]
+    fn proving_prefixes_of_appended_trees() {
+        let old_mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        let appended = ["appended", "elements"].map(String::from);
+        let new_mt = append_elements(get_test_tree(MORE_TEST_ELEMENTS.to_vec()), &appended)
+            .expect("Should have received a valid tree after appending");
+        assert_eq!(new_mt.leaves().len(), 6);
+
+        let proof = prove_prefix(&old_mt, &new_mt).expect("Should have proven the prefix");
+        assert!(verify_prefix_proof(
+            &get_root(&old_mt),
+            &get_root(&new_mt),
+            &proof
+        ));
+        assert!(!verify_prefix_proof(
+            &get_wrong_root(),
+            &get_root(&new_mt),
+            &proof
+        ));
+        assert!(!verify_prefix_proof(
+            &get_root(&old_mt),
+            &get_wrong_root(),
+            &proof
+        ));
+        assert!(!verify_prefix_proof(
+            &get_root(&new_mt),
+            &get_root(&old_mt),
+            &proof
+        ));
+
+        let mut tampered_proof = proof.to_owned();
+        tampered_proof.old_count = 3;
+        assert!(!verify_prefix_proof(
+            &get_root(&old_mt),
+            &get_root(&new_mt),
+            &tampered_proof
+        ));
+
+        let elements = (0..19).map(|i| i.to_string()).collect::<Vec<_>>();
+        for old_count in 1..=elements.len() {
+            let old_mt = create_merkle_tree(&elements[..old_count].to_vec())
+                .expect("Should have received a valid tree given a generated input");
+
+            for new_count in old_count..=elements.len() {
+                let new_mt = create_merkle_tree(&elements[..new_count].to_vec())
+                    .expect("Should have received a valid tree given a generated input");
+                let proof = prove_prefix(&old_mt, &new_mt).expect("Should have proven the prefix");
+                assert!(verify_prefix_proof(
+                    &get_root(&old_mt),
+                    &get_root(&new_mt),
+                    &proof
+                ));
+            }
+        }
+
+        // the trees diverge at index 2, so there is nothing to prove
+        let diverging_mt = get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec());
+        assert!(prove_prefix(&old_mt, &diverging_mt).is_err());
+        assert!(prove_prefix(&new_mt, &get_test_tree(MORE_TEST_ELEMENTS.to_vec())).is_err());
+    }
+
+    #[test]
+    fn salting_leaf_hashes() {
+        let salted = |salt: &[u8]| TreeConfig::default().with_salt(salt);
+
+        assert_ne!(
+            salted(b"pepper").hash_leaf("555-0100"),
+            hash_leaf("555-0100")
+        );
+        assert_ne!(
+            salted(b"pepper").hash_leaf("555-0100"),
+            salted(b"paprika").hash_leaf("555-0100")
+        );
+
+        let mut input = b"pepper".to_vec();
+        input.extend_from_slice(b"555-0100");
+        assert_eq!(
+            salted(b"pepper").hash_leaf("555-0100"),
+            hex::encode(Sha256Hasher.digest(&input))
+        );
+
+        let elements = ["555-0100", "555-0101", "555-0102"].map(String::from);
+        let mt = MerkleTreeBuilder::new()
+            .with_salt(b"pepper")
+            .build(&elements)
+            .expect("Should have received a valid tree given a fixed input");
+        let proof = get_proof(&mt, 1).expect("Should have received a valid proof for index 1");
+        assert!(verify_proof(&get_root(&mt), &proof));
+
+        let path = proof
+            .siblings
+            .iter()
+            .cloned()
+            .zip(proof.directions.iter().copied())
+            .collect::<Vec<_>>();
+        for wrong_config in [salted(b"paprika"), TreeConfig::default()] {
+            let wrong_proof = MerkleProof::from_path_with_config(
+                elements[1].to_owned(),
+                path.to_owned(),
+                wrong_config,
+            );
+            assert!(!verify_proof(&get_root(&mt), &wrong_proof));
+        }
+    }
+
+    #[test]
+    fn getting_root_bytes() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root_bytes = get_root_bytes(&mt).expect("Should have decoded the root");
+        assert_eq!(hex::encode(root_bytes), get_root(&mt).as_str());
+
+        let elements = INCREASINGLY_MORE_TEST_ELEMENTS.map(String::from);
+        let base64_mt = MerkleTreeBuilder::new()
+            .with_encoding(Encoding::Base64)
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+        let base64_root_bytes = get_root_bytes(&base64_mt).expect("Should have decoded the root");
+        let proof = get_proof(&base64_mt, 2).expect("Should have received a valid proof");
+        assert!(verify_proof_bytes(&base64_root_bytes, &proof));
+
+        let mut corrupted_mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        corrupted_mt.root_hash = INVALID_HASH.to_string();
+        assert!(get_root_bytes(&corrupted_mt).is_err());
+        corrupted_mt.root_hash = "abcd".to_string();
+        assert!(get_root_bytes(&corrupted_mt).is_err());
+    }
+
+    #[test]
+    fn verifying_proofs_against_0x_roots() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt).to_string();
+        let proof = get_proof(&mt, 6).expect("Should have received a valid proof for index 6");
+
+        for packed_root in [
+            format!("0x{root}"),
+            format!("0x{}", root.to_uppercase()),
+            format!("0X{}", root.to_uppercase()),
+            root.to_uppercase(),
+            root.to_owned(),
+        ] {
+            assert_eq!(verify_proof_0x(&packed_root, &proof), Ok(true));
+        }
+
+        assert_eq!(
+            verify_proof_0x(&format!("0x{WRONG_ROOT_HASH}"), &proof),
+            Ok(VERIFY_PROOF_FAILED)
+        );
+        assert!(verify_proof_0x(&format!("0x{INVALID_HASH}"), &proof).is_err());
+        assert!(verify_proof_0x(&format!("0x0x{root}"), &proof).is_err());
+    }
+
+    #[test]
+    fn enumerating_paths_for_visualization() {
+        let mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+
+        let paths = all_paths(&mt);
+        assert_eq!(paths.len(), 4);
+        for (index, path) in paths.iter().enumerate() {
+            assert_eq!(
+                *path,
+                get_proof(&mt, index).expect("Should have received a valid proof")
+            );
+        }
+
+        // the internal nodes are the two parents of the leaves, then the root
+        let matrix = path_overlap_matrix(&mt);
+        assert_eq!(
+            matrix,
+            vec![
+                vec![true, false, true],
+                vec![true, false, true],
+                vec![false, true, true],
+                vec![false, true, true],
+            ]
+        );
+
+        let shares_parent = |a: usize, b: usize| matrix[a][0..2] == matrix[b][0..2];
+        assert!(shares_parent(0, 1));
+        assert!(!shares_parent(1, 2));
+        assert!(!shares_parent(0, 3));
+
+        let odd_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let odd_matrix = path_overlap_matrix(&odd_mt);
+        assert_eq!(odd_matrix.len(), odd_mt.leaves().len());
+        assert!(odd_matrix.iter().all(|row| row.len() == 3 + 2 + 1
+            && row[row.len() - 1]
+            && row.iter().filter(|&&on_path| on_path).count() == odd_mt.height()));
+    }
+
+    #[test]
+    fn creating_full_trees() {
+        let elements = EVEN_MORE_TEST_ELEMENTS.map(String::from);
+        let full_mt = create_full_merkle_tree(&elements).expect("Should have built a full tree");
+
+        assert_eq!(full_mt.leaves().len(), 8);
+        assert_eq!(&full_mt.leaves()[5..], ["", "", ""]);
+        assert_ne!(
+            get_root(&full_mt),
+            get_root(&get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec()))
+        );
+
+        for index in 0..elements.len() {
+            let proof = get_proof(&full_mt, index).expect("Should have received a valid proof");
+            assert_eq!(proof.siblings.len(), 3);
+            assert!(verify_proof(&get_root(&full_mt), &proof));
+        }
+
+        // counts that already are a power of two build the same tree as the default padding
+        let elements = INCREASINGLY_MORE_TEST_ELEMENTS.map(String::from);
+        let full_mt = create_full_merkle_tree(&elements).expect("Should have built a full tree");
+        assert_eq!(
+            get_root(&full_mt),
+            get_root(&get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec()))
+        );
+
+        let single_mt =
+            create_full_merkle_tree(&["only".to_string()]).expect("Should have built a full tree");
+        assert_eq!(single_mt.leaves().len(), 2);
+        assert!(create_full_merkle_tree(&[]).is_err());
+    }
+
+    #[test]
+    fn computing_prefix_roots() {
+        let elements = (0..19).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        let roots = prefix_roots(&elements);
+        assert_eq!(roots.len(), elements.len());
+
+        for (k, root) in roots.iter().enumerate() {
+            let prefix_mt = create_merkle_tree(&elements[..=k].to_vec())
+                .expect("Should have received a valid tree given a generated input");
+            assert_eq!(get_root(&prefix_mt), *root);
+        }
+
+        assert!(prefix_roots(&[]).is_empty());
+    }
+
+    #[test]
+    fn verifying_membership_sets() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt);
+        let claims = [(0, "some"), (3, "test"), (6, "use")]
+            .map(|(index, element)| (index, element.to_string()));
+
+        assert_eq!(verify_membership_set(&root, &mt, &claims), Ok(true));
+        assert_eq!(verify_membership_set(&root, &mt, &[]), Ok(true));
+        assert_eq!(
+            verify_membership_set(WRONG_ROOT_HASH, &mt, &claims),
+            Ok(VERIFY_PROOF_FAILED)
+        );
+
+        let mut swapped_claims = claims.to_vec();
+        swapped_claims[1].0 = 4;
+        assert_eq!(
+            verify_membership_set(&root, &mt, &swapped_claims),
+            Ok(false)
+        );
+
+        assert!(verify_membership_set(INVALID_HASH, &mt, &claims).is_err());
+        assert!(verify_membership_set(&root, &mt, &[(8, "again".to_string())]).is_err());
+    }
+
+    #[test]
+    fn compressing_proofs_of_sparse_trees() {
+        let sparse_mt = create_sparse_merkle_tree(&["only".to_string()], 8)
+            .expect("Should have received a valid sparse tree");
+        let root = get_sparse_tree_root(&sparse_mt);
+        assert_eq!(sparse_mt.depth(), 8);
+        assert_eq!(sparse_mt.element_count(), 1);
+
         let proof =
-            get_proof(&mt, 0).expect("Should have received a valid proof for the first element");
+            get_compressed_proof(&sparse_mt, 0).expect("Should have received a valid proof");
+        assert_eq!(proof.explicit_siblings(), 0);
+        assert!(verify_compressed_proof(&root, &proof));
+        assert!(!verify_compressed_proof(&get_wrong_root(), &proof));
+
+        let mut tampered_proof = proof.to_owned();
+        tampered_proof.element = "tampered".to_string();
+        assert!(!verify_compressed_proof(&root, &tampered_proof));
+
+        // only the siblings next to other elements are carried
+        let elements = TEST_ELEMENTS.map(String::from);
+        let sparse_mt = create_sparse_merkle_tree(&elements, 8)
+            .expect("Should have received a valid sparse tree");
+        let root = get_sparse_tree_root(&sparse_mt);
+        let dense_mt =
+            create_merkle_tree_with_config(&elements, TreeConfig::default().with_fixed_depth(8))
+                .expect("Should have received a valid tree given const test inputs");
+        assert_eq!(get_root(&dense_mt), root);
+
+        let uncompressed_proof =
+            get_proof(&dense_mt, 2).expect("Should have received a valid proof");
+        let proof =
+            get_compressed_proof(&sparse_mt, 2).expect("Should have received a valid proof");
+        assert_eq!(uncompressed_proof.siblings.len(), 8);
+        assert_eq!(proof.explicit_siblings(), 1);
+        assert!(verify_compressed_proof(&root, &proof));
+        assert!(verify_proof(&root, &uncompressed_proof));
+
+        let mut truncated_proof = proof;
+        truncated_proof.siblings.clear();
+        assert!(!verify_compressed_proof(&root, &truncated_proof));
+
+        assert!(create_sparse_merkle_tree(&elements, 1).is_err());
+        assert!(get_compressed_proof(&sparse_mt, 3).is_err());
+
+        // only the nodes over the elements are built, however deep the tree
+        let sparse_mt = create_sparse_merkle_tree(&elements, 64)
+            .expect("Should have received a valid sparse tree");
+        let root = get_sparse_tree_root(&sparse_mt);
+        for index in 0..elements.len() {
+            let proof = get_compressed_proof(&sparse_mt, index)
+                .expect("Should have received a valid proof");
+            assert!(proof.explicit_siblings() <= 2);
+            assert!(verify_compressed_proof(&root, &proof));
+        }
+
+        let empty_mt =
+            create_sparse_merkle_tree(&[], 32).expect("Should have received a valid sparse tree");
+        assert_eq!(
+            get_sparse_tree_root(&empty_mt).to_string(),
+            empty_mt.empty_hashes[32]
+        );
+    }
+
+    #[test]
+    fn caching_the_empty_node_hash() {
+        assert_eq!(empty_node_hash(), hash_leaf(""));
+        assert!(std::ptr::eq(empty_node_hash(), empty_node_hash()));
+
+        // the padding leaf of an odd tree is recognisable as the sibling of the last element
+        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 2).expect("Should have received a valid proof for index 2");
+        assert_eq!(proof.siblings[0], empty_node_hash());
+    }
+
+    #[test]
+    fn rejecting_self_referential_proofs() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt);
+        let leaf_hash = hash_leaf(INCREASINGLY_MORE_TEST_ELEMENTS[2]);
+
+        let mut forged_proof =
+            get_proof(&mt, 2).expect("Should have received a valid proof for index 2");
+        forged_proof.siblings[0] = leaf_hash.to_owned();
+        assert_eq!(verify_proof_checked(&root, &forged_proof), Ok(false));
+
+        for direction in [false, true] {
+            let forged_proof = MerkleProof::from_path(
+                INCREASINGLY_MORE_TEST_ELEMENTS[2].to_string(),
+                vec![(leaf_hash.to_owned(), direction); 3],
+            );
+            assert_eq!(verify_proof_checked(&root, &forged_proof), Ok(false));
+        }
+
+        // a duplicate element is a legitimate sibling equal to the leaf's own hash
+        let duplicated_mt = get_test_tree(vec!["some", "some"]);
+        let proof = get_proof(&duplicated_mt, 0).expect("Should have received a valid proof");
+        assert_eq!(proof.siblings, vec![hash_leaf("some")]);
+        assert_eq!(
+            verify_proof_checked(&get_root(&duplicated_mt), &proof),
+            Ok(true)
+        );
+
+        // as is the last node of an odd row paired with itself
+        let elements = TEST_ELEMENTS.map(String::from).to_vec();
+        let duplicate_last_mt = create_merkle_tree_with_config(
+            &elements,
+            TreeConfig::default().with_odd_handling(OddHandling::DuplicateLast),
+        )
+        .expect("Should have received a valid tree given const test inputs");
+        let proof = get_proof(&duplicate_last_mt, 2).expect("Should have received a valid proof");
+        assert_eq!(proof.siblings[0], hash_leaf(TEST_ELEMENTS[2]));
+        assert_eq!(
+            verify_proof_checked(&get_root(&duplicate_last_mt), &proof),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn recovering_proof_indices() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+
+        for index in 0..mt.leaves().len() {
+            let proof = get_proof(&mt, index).expect("Should have received a valid proof");
+            assert_eq!(proof_index(&proof), Some(index));
+        }
+
+        let odd_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        for index in 0..odd_mt.leaves().len() {
+            let proof = get_proof_allow_padding(&odd_mt, index)
+                .expect("Should have received a valid proof");
+            assert_eq!(proof_index(&proof), Some(index));
+        }
+
+        // a path of 64 levels still fits a 64 bit index, one more level doesn't whatever its
+        // directions
+        let path = vec![(get_wrong_root().to_string(), true); usize::BITS as usize];
+        let proof = MerkleProof::from_path("element".to_string(), path.to_owned());
+        assert_eq!(proof_index(&proof), Some(usize::MAX));
+
+        for is_left_child in [true, false] {
+            let mut long_path = path.to_owned();
+            long_path.push((get_wrong_root().to_string(), is_left_child));
+            let proof = MerkleProof::from_path("element".to_string(), long_path);
+            assert_eq!(proof_index(&proof), None);
+        }
+    }
+
+    #[test]
+    fn streaming_aggregate_proof_verification() {
+        let elements = (0..1024).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given a generated input");
+
+        let proof = get_aggregate_proof(&mt, 300, 812)
+            .expect("Should have received a valid proof for the elements [300,812)");
+
+        assert!(verify_aggregate_proof_streaming(&get_root(&mt), &proof));
+        assert_eq!(
+            verify_aggregate_proof_streaming(&get_wrong_root(), &proof),
+            verify_aggregate_proof(&get_wrong_root(), &proof)
+        );
+
+        let odd_mt = create_merkle_tree(&elements[..1000].to_vec())
+            .expect("Should have received a valid tree given a generated input");
+        for (start, end) in [
+            (0, 1),
+            (0, 1000),
+            (1, 512),
+            (487, 999),
+            (998, 1000),
+            (999, 1000),
+        ] {
+            let proof = get_aggregate_proof(&odd_mt, start, end)
+                .expect("Should have received a valid proof for a range within the leaves");
+
+            assert!(verify_aggregate_proof(&get_root(&odd_mt), &proof));
+            assert!(verify_aggregate_proof_streaming(&get_root(&odd_mt), &proof));
+            assert!(verify_aggregate_proof_streaming(
+                &get_root(&odd_mt),
+                &proof.minimize()
+            ));
+        }
+    }
+
+    #[test]
+    fn building_trees_from_owned_elements() {
+        let elements = EVEN_MORE_TEST_ELEMENTS.map(String::from).to_vec();
+        let expected_root = get_root(&get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec()));
+
+        let mt = create_merkle_tree_owned(elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        assert_eq!(get_root(&mt), expected_root);
+        assert_eq!(mt.real_leaf_count(), 5);
+        assert_eq!(mt.leaves().len(), 6);
+
+        let fixed_mt = create_merkle_tree_owned_with_config(
+            EVEN_MORE_TEST_ELEMENTS.map(String::from).to_vec(),
+            TreeConfig::default().with_fixed_depth(2),
+        );
+        assert!(fixed_mt.is_err());
+
+        assert!(create_merkle_tree_owned(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn building_large_trees() {
+        use crate::merkle_core::CoreTree;
+
+        let elements = (0..1025).map(|i| i.to_string()).collect::<Vec<_>>();
+        let element_bytes = elements.iter().map(|e| e.as_bytes()).collect::<Vec<_>>();
+
+        let mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given a generated input");
+        let core_tree = CoreTree::build(sha256, &element_bytes)
+            .expect("Should have received a valid core tree given a generated input");
+
+        assert_eq!(get_root(&mt), hex::encode(core_tree.root()));
+        assert!(validate_integrity(&mt));
+
+        let duplicated_mt = MerkleTreeBuilder::new()
+            .with_odd_handling(OddHandling::DuplicateLast)
+            .build(&elements)
+            .expect("Should have received a valid tree given a generated input");
+        assert!(validate_integrity(&duplicated_mt));
+    }
+
+    #[test]
+    fn matching_the_no_std_core() {
+        use crate::merkle_core::{self, CoreTree};
+
+        for input in [
+            TEST_ELEMENTS.to_vec(),
+            MORE_TEST_ELEMENTS.to_vec(),
+            EVEN_MORE_TEST_ELEMENTS.to_vec(),
+            YET_MORE_TEST_ELEMENTS.to_vec(),
+            LOTS_MORE_TEST_ELEMENTS.to_vec(),
+            INCREASINGLY_MORE_TEST_ELEMENTS.to_vec(),
+            vec!["only"],
+        ] {
+            let mt = get_test_tree(input.to_owned());
+            let elements = input.iter().map(|e| e.as_bytes()).collect::<Vec<_>>();
+            let core_tree = CoreTree::build(sha256, &elements)
+                .expect("Should have received a valid core tree given const test inputs");
+
+            assert_eq!(get_root(&mt), hex::encode(core_tree.root()));
+
+            for (index, element) in mt.leaves().iter().enumerate() {
+                let proof = get_proof_allow_padding(&mt, index)
+                    .expect("Should have received a valid proof");
+                let core_proof = core_tree
+                    .proof(index)
+                    .expect("Should have received a valid core proof");
+
+                let core_siblings = core_proof
+                    .siblings
+                    .iter()
+                    .map(|sibling| sibling.map(hex::encode).unwrap_or_default())
+                    .collect::<Vec<_>>();
+                assert_eq!(core_siblings, proof.siblings);
+                assert_eq!(core_proof.directions, proof.directions);
+                assert!(merkle_core::verify(
+                    sha256,
+                    &core_tree.root(),
+                    element.as_bytes(),
+                    &core_proof
+                ));
+            }
+        }
+
+        let core_hashed_mt = create_merkle_tree_with_config(
+            &TEST_ELEMENTS.map(String::from),
+            TreeConfig::default().with_hasher(CoreHasher(sha256)),
+        )
+        .expect("Should have received a valid tree given const test inputs");
+        assert_eq!(
+            get_root(&core_hashed_mt),
+            get_root(&get_test_tree(TEST_ELEMENTS.to_vec()))
+        );
+        assert!(CoreTree::build(sha256, &[]).is_err());
+    }
+
+    #[test]
+    fn finding_lowest_common_ancestors() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let leaves = INCREASINGLY_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|e| hash_leaf(e))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lowest_common_ancestor(&mt, 0, 7).expect("Should have found the ancestor of 0 and 7"),
+            get_root(&mt).to_string()
+        );
+        assert_eq!(
+            lowest_common_ancestor(&mt, 0, 1).expect("Should have found the ancestor of 0 and 1"),
+            hash_node(&leaves[0], &leaves[1])
+        );
+        assert_eq!(
+            lowest_common_ancestor(&mt, 6, 4).expect("Should have found the ancestor of 6 and 4"),
+            hash_node(
+                &hash_node(&leaves[4], &leaves[5]),
+                &hash_node(&leaves[6], &leaves[7])
+            )
+        );
+        assert_eq!(
+            lowest_common_ancestor(&mt, 3, 3).expect("Should have found the ancestor of 3 and 3"),
+            leaves[3]
+        );
+        assert!(lowest_common_ancestor(&mt, 0, 8).is_err());
+    }
+
+    #[test]
+    fn predicting_hash_op_counts() {
+        for element_count in [1, 2, 3, 5, 7, 8, 9, 100, 1000] {
+            let elements = (0..element_count)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>();
+
+            let before = HASH_COUNT.with(|count| count.get());
+            create_merkle_tree(&elements)
+                .expect("Should have received a valid tree given a generated input");
+            let hashes = HASH_COUNT.with(|count| count.get()) - before;
+
+            assert_eq!(hash_op_count(element_count), Some(hashes));
+        }
+
+        assert_eq!(hash_op_count(3), Some(7));
+        assert_eq!(hash_op_count(usize::MAX), None);
+        assert_eq!(hash_op_count(1 << 40), Some((1 << 41) - 1));
+    }
+
+    #[test]
+    fn observing_hash_operations() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Debug, Default)]
+        struct CountingObserver {
+            hashes: AtomicUsize,
+            input_bytes: AtomicUsize,
+        }
+
+        impl HashObserver for CountingObserver {
+            fn on_hash(&self, input_len: usize) {
+                self.hashes.fetch_add(1, Ordering::Relaxed);
+                self.input_bytes.fetch_add(input_len, Ordering::Relaxed);
+            }
+        }
+
+        let elements = LOTS_MORE_TEST_ELEMENTS.map(String::from).to_vec();
+        let observer = Arc::new(CountingObserver::default());
+        let mt = MerkleTreeBuilder::new()
+            .with_hash_observer(observer.to_owned())
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        assert_eq!(
+            Some(observer.hashes.load(Ordering::Relaxed)),
+            hash_op_count(elements.len())
+        );
+        assert_eq!(
+            get_root(&mt),
+            get_root(&get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec()))
+        );
+
+        // the 8 leaves are hashed over their elements, the padding being empty, and the 7
+        // internal nodes over the hex digests of their two children
+        let leaf_bytes = elements.iter().map(String::len).sum::<usize>();
+        assert_eq!(
+            observer.input_bytes.load(Ordering::Relaxed),
+            leaf_bytes + 7 * 128
+        );
 
-        assert!(verify_proof(get_root(&mt), &proof));
+        // the proof carries the observer along, one leaf hash and one per sibling to verify it
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
+        let before = observer.hashes.load(Ordering::Relaxed);
+        assert!(verify_proof(&get_root(&mt), &proof));
         assert_eq!(
-            verify_proof(INVALID_HASH.into(), &proof),
-            VERIFY_PROOF_FAILED
+            observer.hashes.load(Ordering::Relaxed) - before,
+            1 + mt.height()
         );
     }
 
     #[test]
-    fn updating_elements() {
-        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
-        let new_element = "extra";
-        let mut elements = TEST_ELEMENTS.to_vec();
-        elements.insert(1, new_element);
-        let expected_root = get_expected_root_hash(elements);
+    fn separating_node_children() {
+        let elements = EVEN_MORE_TEST_ELEMENTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let leaves = ["some", "more"].map(hash_leaf);
 
-        let updated_mt = update_element(mt, 1, new_element).expect(
-            "Should have received a valid tree from the implementation given these known inputs",
+        let mt = MerkleTreeBuilder::new()
+            .with_node_separator(true)
+            .build(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        assert_ne!(
+            get_root(&mt),
+            get_expected_root_hash(EVEN_MORE_TEST_ELEMENTS.to_vec())
+        );
+        assert_eq!(
+            level_hashes(&mt, 1).expect("Should have received the level above the leaves")[0],
+            hasher(&format!("{}:{}", leaves[0], leaves[1]))
         );
 
-        assert_eq!(get_root(&updated_mt), expected_root);
+        for proof in get_all_proofs(&mt).expect("Should have received a proof for every leaf") {
+            assert!(verify_proof(&get_root(&mt), &proof));
+        }
+
+        let aggregate_proof = get_aggregate_proof(&mt, 1, 4)
+            .expect("Should have received a valid proof for the elements [1,4)");
+        assert!(verify_aggregate_proof(&get_root(&mt), &aggregate_proof));
     }
 
     #[test]
-    fn updating_elements_out_of_bounds() {
-        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
-        let new_element = "and this is what it means to go even further beyond!";
+    fn encoding_proofs() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
 
-        let oob = mt.leaves.len();
-        let result = update_element(mt, oob, new_element);
+        for proof in get_all_proofs(&mt).expect("Should have received a proof for every leaf") {
+            let data = proof
+                .encode()
+                .expect("Should have encoded a proof of 32 byte digests");
+            let decoded_proof =
+                MerkleProof::decode(&data).expect("Should have decoded a freshly encoded proof");
 
-        assert!(result.is_err());
+            assert_eq!(decoded_proof, proof);
+            assert_eq!(decoded_proof.encode(), Ok(data));
+            assert!(verify_proof(&get_root(&mt), &decoded_proof));
+        }
+
+        let proof = get_proof(&mt, 4).expect("Should have received a valid proof for index 4");
+        assert!(proof.siblings.contains(&String::new()));
+
+        let data = proof
+            .encode()
+            .expect("Should have encoded a proof of 32 byte digests");
+        assert!(MerkleProof::decode(&data[..data.len() - 1]).is_err());
+        assert!(MerkleProof::decode(&data[..4]).is_err());
+
+        // the 3 siblings leave 5 unused bits in each bitmap, which have to stay clear
+        let bitmap_start = 8 + proof.element.len() + 8 + 8;
+        for bitmap in [bitmap_start, bitmap_start + 1] {
+            let mut padded = data.clone();
+            padded[bitmap] |= 0b1000_0000;
+            assert!(MerkleProof::decode(&padded).is_err());
+        }
+
+        let mut short_proof = get_proof(&mt, 0).expect("Should have received a valid proof");
+        short_proof.siblings[0] = "abcd".to_string();
+        assert!(short_proof.encode().is_err());
     }
 
     #[test]
-    fn generating_trees_of_varying_heights() {
-        let mt_expected_root = get_expected_root_hash(TEST_ELEMENTS.to_vec());
-        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+    fn verifying_proof_batches() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let mut proofs = [0, 3, 6]
+            .iter()
+            .map(|&index| get_proof(&mt, index).expect("Should have received a valid proof"))
+            .collect::<Vec<_>>();
 
-        let more_mt_expected_root = get_expected_root_hash(MORE_TEST_ELEMENTS.to_vec());
-        let more_mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        assert_eq!(verify_batch(&get_root(&mt), &proofs), Ok(()));
 
-        let even_more_mt_expected_root = get_expected_root_hash(EVEN_MORE_TEST_ELEMENTS.to_vec());
-        let even_more_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        proofs[1].element = "tampered".to_string();
 
-        let yet_more_mt_expected_root = get_expected_root_hash(YET_MORE_TEST_ELEMENTS.to_vec());
-        let yet_more_mt = get_test_tree(YET_MORE_TEST_ELEMENTS.to_vec());
+        assert_eq!(verify_batch(&get_root(&mt), &proofs), Err(1));
+        assert_eq!(verify_batch(&get_wrong_root(), &proofs), Err(0));
+    }
 
-        let lots_more_mt_expected_root = get_expected_root_hash(LOTS_MORE_TEST_ELEMENTS.to_vec());
-        let lots_more_mt = get_test_tree(LOTS_MORE_TEST_ELEMENTS.to_vec());
+    #[test]
+    fn verifying_proofs_against_root_bytes() {
+        let mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 2).expect("Should have received a valid proof");
 
-        let increasingly_more_mt_expected_root =
-            get_expected_root_hash(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
-        let increasingly_more_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root_bytes: [u8; 32] = hex::decode(get_root(&mt).as_str())
+            .expect("Should have received a hex root")
+            .try_into()
+            .expect("Should have received a 32 byte root");
 
-        assert_eq!(get_root(&mt), mt_expected_root);
-        assert_eq!(get_root(&more_mt), more_mt_expected_root);
-        assert_eq!(get_root(&even_more_mt), even_more_mt_expected_root);
-        assert_eq!(get_root(&yet_more_mt), yet_more_mt_expected_root);
-        assert_eq!(get_root(&lots_more_mt), lots_more_mt_expected_root);
+        assert!(verify_proof_bytes(&root_bytes, &proof));
+        assert!(!verify_proof_bytes(&[0; 32], &proof));
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn verifying_signed_proofs() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+
+        let mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt);
+        let root_bytes = hex::decode(root.as_str()).expect("Should have received a hex root");
+        let signature = signing_key.sign(&root_bytes).to_bytes();
+
+        let proof = get_proof(&mt, 1).expect("Should have received a valid proof");
         assert_eq!(
-            get_root(&increasingly_more_mt),
-            increasingly_more_mt_expected_root
+            verify_signed_proof(&root, &signature, &public_key, &proof),
+            Ok(true)
+        );
+
+        let mut tampered_signature = signature;
+        tampered_signature[0] ^= 1;
+        assert_eq!(
+            verify_signed_proof(&root, &tampered_signature, &public_key, &proof),
+            Ok(false)
+        );
+
+        let foreign_proof = get_proof(&get_test_tree(TEST_ELEMENTS.to_vec()), 1)
+            .expect("Should have received a valid proof");
+        assert_eq!(
+            verify_signed_proof(&root, &signature, &public_key, &foreign_proof),
+            Ok(false)
         );
+
+        assert!(verify_signed_proof(&root, &signature[..63], &public_key, &proof).is_err());
+        assert!(verify_signed_proof(&root, &signature, &public_key[..31], &proof).is_err());
     }
 
     #[test]
-    fn verifying_aggregate_proofs() {
+    fn verifying_proofs_with_inverted_directions() {
         let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 5).expect("Should have received a valid proof");
 
-        let proof = get_aggregate_proof(&mt, 2, 6)
-            .expect("Should have received a valid proof for the elements [2,6)");
+        let mut inverted_proof = get_proof(&mt, 5).expect("Should have received a valid proof");
+        inverted_proof.directions.iter_mut().for_each(|d| *d = !*d);
+
+        assert!(!verify_proof(&get_root(&mt), &inverted_proof));
+        assert!(verify_proof_either_direction(
+            &get_root(&mt),
+            &inverted_proof
+        ));
+        assert!(verify_proof_either_direction(&get_root(&mt), &proof));
+        assert!(!verify_proof_either_direction(
+            &get_wrong_root(),
+            &inverted_proof
+        ));
+    }
+
+    #[test]
+    fn matching_proofs_to_the_same_tree() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let other_mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+
+        let proof = get_proof(&mt, 1).expect("Should have received a valid proof");
+        let sibling_proof = get_proof(&mt, 6).expect("Should have received a valid proof");
+        let foreign_proof = get_proof(&other_mt, 1).expect("Should have received a valid proof");
+
+        assert!(same_tree(&proof, &sibling_proof));
+        assert!(!same_tree(&proof, &foreign_proof));
+    }
+
+    #[test]
+    fn generating_proofs_concurrently() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<MerkleTree>();
+
+        let mt = Arc::new(get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec()));
+
+        let handles = (0..mt.leaves().len())
+            .map(|index| {
+                let mt = Arc::clone(&mt);
+                thread::spawn(move || {
+                    let proof = get_proof(&mt, index).expect("Should have received a valid proof");
+                    verify_proof(&get_root(&mt), &proof)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert!(handle
+                .join()
+                .expect("Proof generating thread should not panic"));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn generating_proofs_in_parallel() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let indices = [6, 0, 4, 2];
+
+        let proofs =
+            get_proofs_parallel(&mt, &indices).expect("Should have received a valid proof each");
+
+        assert_eq!(proofs.len(), indices.len());
+
+        for (proof, &index) in proofs.iter().zip(indices.iter()) {
+            assert!(verify_proof(&get_root(&mt), proof));
+            assert_eq!(
+                proof,
+                &get_proof(&mt, index).expect("Should have received a valid proof")
+            );
+        }
+
+        assert!(get_proofs_parallel(&mt, &[0, mt.leaves().len()]).is_err());
+    }
+
+    #[test]
+    fn extending_roots() {
+        let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        let root = get_root(&mt);
+        let sibling = hash_leaf("sibling");
 
-        assert!(verify_aggregate_proof(get_root(&mt), &proof));
         assert_eq!(
-            verify_aggregate_proof(INVALID_HASH.into(), &proof),
-            VERIFY_PROOF_FAILED
+            extend_root(&root, &sibling, true),
+            hash_node(&sibling, &root)
+        );
+        assert_eq!(
+            extend_root(&root, &sibling, false),
+            hash_node(&root, &sibling)
+        );
+
+        let proof = get_proof(&mt, 5).expect("Should have received a valid proof for index 5");
+        let subtree_root = get_partial_proof(&mt, 5, 2)
+            .map(|partial_proof| proof_root(&partial_proof))
+            .expect("Should have received a valid partial proof for index 5");
+
+        assert_eq!(
+            extend_root(&subtree_root, &proof.siblings[2], proof.directions[2]),
+            root.to_string()
         );
     }
 
     #[test]
-    fn verifying_aggregate_proofs_out_of_bounds() {
+    fn sharing_the_path_above_converged_boundaries() {
         let mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
 
-        let oob = mt.leaves.len();
-        let overflow_result = get_aggregate_proof(&mt, 0, oob);
-        let invert_result = get_aggregate_proof(&mt, 1, 0);
-        let eq_result = get_aggregate_proof(&mt, 2, 2);
+        let proof = get_aggregate_proof(&mt, 3, 4)
+            .expect("Should have received a valid proof for the elements [3,4)");
+        let single_proof =
+            get_proof(&mt, 3).expect("Should have received a valid proof for index 3");
 
-        assert!(overflow_result.is_err());
-        assert!(invert_result.is_err());
-        assert!(eq_result.is_err());
+        // two boundaries would need a start/end pair at each of the 3 levels
+        assert_eq!(proof.siblings.len(), 3);
+        assert_eq!(proof.siblings, single_proof.siblings);
+        assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+        assert!(verify_aggregate_proof_streaming(&get_root(&mt), &proof));
+        assert_eq!(
+            verify_aggregate_proof_checked(&get_root(&mt), &proof),
+            Ok(true)
+        );
+
+        let proof = get_aggregate_proof(&mt, 2, 4)
+            .expect("Should have received a valid proof for the elements [2,4)");
+        assert_eq!(proof.siblings.len(), 4);
+        assert!(verify_aggregate_proof(&get_root(&mt), &proof));
+
+        let minimized_proof = proof.minimize();
+        assert_eq!(minimized_proof.siblings.len(), 2);
+        assert!(verify_aggregate_proof(&get_root(&mt), &minimized_proof));
+        assert!(verify_aggregate_proof_streaming(
+            &get_root(&mt),
+            &minimized_proof
+        ));
+
+        let odd_mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        for (start, end) in [(4, 5), (4, 6), (5, 6), (0, 2)] {
+            let proof = get_aggregate_proof(&odd_mt, start, end)
+                .expect("Should have received a valid proof for a range within the leaves");
+            assert!(verify_aggregate_proof(&get_root(&odd_mt), &proof));
+            assert!(verify_aggregate_proof_streaming(&get_root(&odd_mt), &proof));
+            assert!(verify_aggregate_proof(
+                &get_root(&odd_mt),
+                &proof.minimize()
+            ));
+        }
     }
 
     #[test]
-    fn test_root() {
-        let expected_root = get_expected_root_hash(TEST_ELEMENTS.to_vec());
+    fn tracing_proof_verification() {
+        let mt = get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 4).expect("Should have received a valid proof for index 4");
 
-        let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let (verified, trace) = verify_proof_trace(&get_root(&mt), &proof);
 
-        assert_eq!(get_root(&mt), expected_root);
+        assert!(verified);
+        assert_eq!(trace.len(), proof.siblings.len() + 1);
+        assert_eq!(trace[0], hash_leaf("elements"));
+        assert_eq!(get_root(&mt), trace[trace.len() - 1]);
+
+        for (level, hash) in trace.iter().enumerate() {
+            let level_hashes =
+                level_hashes(&mt, level).expect("Should have received every level of the tree");
+            assert_eq!(hash, &level_hashes[4 >> level]);
+        }
+
+        let (verified, wrong_trace) = verify_proof_trace(&get_wrong_root(), &proof);
+        assert!(!verified);
+        assert_eq!(wrong_trace, trace);
     }
 
     #[test]
-    fn test_proof() {
+    fn proving_weighted_elements() {
+        let elements = EVEN_MORE_TEST_ELEMENTS
+            .iter()
+            .zip(1..)
+            .map(|(e, weight)| (e.to_string(), weight))
+            .collect::<Vec<_>>();
+        let mt = create_weighted_merkle_tree(&elements)
+            .expect("Should have received a valid tree given const test inputs");
+
+        let mut expected_leaf = b"valid".to_vec();
+        expected_leaf.extend_from_slice(&3u64.to_le_bytes());
+        assert_eq!(
+            level_hashes(&mt, 0).expect("Should have received the leaf level")[2],
+            hex::encode(Sha256Hasher.digest(&expected_leaf))
+        );
+        assert_ne!(
+            get_root(&mt),
+            get_root(&get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec()))
+        );
+        assert_eq!(mt.weight(5), Some(0));
+
+        let mut proof = get_proof(&mt, 2).expect("Should have received a valid proof for index 2");
+        assert_eq!(proof.weight(), Some(3));
+        assert!(verify_proof(&get_root(&mt), &proof));
+
+        let decoded_proof = MerkleProof::from_bytes(&proof.to_bytes())
+            .expect("Should have decoded a freshly encoded proof");
+        assert!(verify_proof(&get_root(&mt), &decoded_proof));
+        let decoded_proof =
+            MerkleProof::decode(&proof.encode().expect("Should have encoded a compact proof"))
+                .expect("Should have decoded a compact proof");
+        assert!(verify_proof(&get_root(&mt), &decoded_proof));
+
+        proof.weight = Some(4);
+        assert!(!verify_proof(&get_root(&mt), &proof));
+        proof.weight = None;
+        assert!(!verify_proof(&get_root(&mt), &proof));
+
+        let loaded_mt =
+            MerkleTree::from_bytes(&mt.to_bytes()).expect("Should have loaded a serialized tree");
+        assert_eq!(get_root(&loaded_mt), get_root(&mt));
+        assert!(validate_integrity(&loaded_mt));
+        assert!(get_aggregate_proof(&mt, 1, 3).is_err());
+    }
+
+    #[test]
+    fn committing_to_contexts() {
+        let mt = get_test_tree(MORE_TEST_ELEMENTS.to_vec());
+        let proof = get_proof(&mt, 3).expect("Should have received a valid proof");
+
+        let commitment = commit_with_context(&mt, 1_700_000_000, b"nonce");
+
+        assert_ne!(
+            commitment,
+            commit_with_context(&mt, 1_700_000_000, b"other")
+        );
+        assert_ne!(
+            commitment,
+            commit_with_context(&mt, 1_700_000_001, b"nonce")
+        );
+        assert!(verify_context_commitment(
+            &commitment,
+            &get_root(&mt),
+            1_700_000_000,
+            b"nonce",
+            &proof
+        ));
+        assert!(!verify_context_commitment(
+            &commitment,
+            &get_root(&mt),
+            1_700_000_000,
+            b"other",
+            &proof
+        ));
+        assert!(!verify_context_commitment(
+            &commitment,
+            &get_wrong_root(),
+            1_700_000_000,
+            b"nonce",
+            &proof
+        ));
+    }
+
+    #[test]
+    fn identifying_trees() {
         let mt = get_test_tree(TEST_ELEMENTS.to_vec());
+        let padded_mt = get_test_tree(vec!["some", "test", "elements", ""]);
 
-        for i in 0..TEST_ELEMENTS.len() {
-            let proof = get_proof(&mt, i)
-                .expect("Should have received a valid proof for any of the original elements");
+        assert_eq!(get_root(&mt), get_root(&padded_mt));
+        assert_ne!(mt.tree_id(), padded_mt.tree_id());
+        assert_eq!(
+            mt.tree_id(),
+            get_test_tree(TEST_ELEMENTS.to_vec()).tree_id()
+        );
+    }
+
+    #[test]
+    fn building_kary_trees() {
+        let elements = (0..16).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        let kary_mt =
+            create_karytree(&elements, 4).expect("Should have received a valid arity-4 tree");
+        let binary_mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given a generated input");
+
+        for index in [0, 5, 15] {
+            let proof =
+                get_kary_proof(&kary_mt, index).expect("Should have received a valid proof");
+
+            assert_eq!(proof.siblings.len(), 2);
+            assert!(proof.siblings.iter().all(|level| level.len() == 3));
+            assert!(verify_kary_proof(&get_kary_root(&kary_mt), &proof));
+            assert!(!verify_kary_proof(&get_wrong_root(), &proof));
+        }
+
+        assert_eq!(kary_mt.height(), 2);
+        assert_eq!(binary_mt.height(), 4);
+        assert_ne!(get_kary_root(&kary_mt), get_root(&binary_mt));
+
+        let odd_elements = EVEN_MORE_TEST_ELEMENTS.map(String::from);
+        let binary_kary_mt =
+            create_karytree(&odd_elements, 2).expect("Should have received a valid arity-2 tree");
+        assert_eq!(
+            get_kary_root(&binary_kary_mt),
+            get_root(&get_test_tree(EVEN_MORE_TEST_ELEMENTS.to_vec()))
+        );
+
+        let ternary_mt =
+            create_karytree(&odd_elements, 3).expect("Should have received a valid arity-3 tree");
+        for index in 0..ternary_mt.leaves().len() {
+            let proof =
+                get_kary_proof(&ternary_mt, index).expect("Should have received a valid proof");
+            assert!(verify_kary_proof(&get_kary_root(&ternary_mt), &proof));
+        }
+
+        assert!(create_karytree(&elements, 1).is_err());
+
+        // an arity wider than the elements pads them out to a single parent node
+        let wide_mt = create_karytree(&TEST_ELEMENTS.map(String::from), 4)
+            .expect("Should have received a valid arity-4 tree");
+        assert_eq!(wide_mt.leaves().len(), 4);
+        assert_eq!(wide_mt.height(), 1);
+        let proof = get_kary_proof(&wide_mt, 2).expect("Should have received a valid proof");
+        assert!(verify_kary_proof(&get_kary_root(&wide_mt), &proof));
+
+        assert!(create_karytree(&elements, usize::MAX).is_err());
+        let limited_mt = create_karytree_with_config(
+            &elements,
+            1 << 40,
+            TreeConfig::default().with_max_leaves(1024),
+        );
+        assert!(limited_mt.is_err());
+    }
+
+    #[test]
+    fn verifying_proofs_of_any_arity() {
+        let elements = (0..16).map(|i| i.to_string()).collect::<Vec<_>>();
+        let kary_mt =
+            create_karytree(&elements, 4).expect("Should have received a valid arity-4 tree");
+        let binary_mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given a generated input");
+
+        for index in [0, 5, 15] {
+            let binary_proof =
+                get_proof(&binary_mt, index).expect("Should have received a valid proof");
+            assert_eq!(binary_proof.arity(), 2);
+            assert!(verify_proof(&get_root(&binary_mt), &binary_proof));
+            assert!(!verify_proof(&get_kary_root(&kary_mt), &binary_proof));
+
+            let kary_proof = MerkleProof::from(
+                get_kary_proof(&kary_mt, index).expect("Should have received a valid proof"),
+            );
+            assert_eq!(kary_proof.arity(), 4);
+            assert_eq!(kary_proof.siblings.len(), 6);
+            assert!(verify_proof(&get_kary_root(&kary_mt), &kary_proof));
+            assert!(!verify_proof(&get_root(&binary_mt), &kary_proof));
+            assert!(!verify_proof(&get_wrong_root(), &kary_proof));
+        }
+
+        // a sibling marked left of one on the right puts the path's node nowhere
+        let mut malformed_proof = MerkleProof::from(
+            get_kary_proof(&kary_mt, 5).expect("Should have received a valid proof"),
+        );
+        malformed_proof.directions[0..3].copy_from_slice(&[false, true, false]);
+        assert!(!verify_proof(&get_kary_root(&kary_mt), &malformed_proof));
+
+        malformed_proof.siblings.pop();
+        malformed_proof.directions.pop();
+        assert_eq!(proof_root(&malformed_proof), "");
+    }
+
+    #[test]
+    fn serializing_proofs_of_any_arity() {
+        let elements = (0..16).map(|i| i.to_string()).collect::<Vec<_>>();
+        let kary_mt =
+            create_karytree(&elements, 4).expect("Should have received a valid arity-4 tree");
+        let root = get_kary_root(&kary_mt);
+
+        for index in [0, 5, 15] {
+            let proof = MerkleProof::from(
+                get_kary_proof(&kary_mt, index).expect("Should have received a valid proof"),
+            );
+            assert_eq!(proof_index(&proof), Some(index));
+
+            let from_bytes = MerkleProof::from_bytes(&proof.to_bytes())
+                .expect("Should have loaded a serialized proof");
+            let decoded =
+                MerkleProof::decode(&proof.encode().expect("Should have encoded a compact proof"))
+                    .expect("Should have decoded a compact proof");
+            let from_json =
+                MerkleProof::from_json(&proof.to_json()).expect("Should have parsed a JSON proof");
+
+            for loaded in [from_bytes, decoded, from_json] {
+                assert_eq!(loaded, proof);
+                assert!(verify_proof(&root, &loaded));
+            }
+
+            // the binary-only verifiers turn a k-ary proof down rather than misreading it
+            assert_eq!(verify_proof_trace(&root, &proof), (false, Vec::new()));
+            assert!(verify_proof_either_direction(&root, &proof));
+            assert!(!verify_proof_either_direction(&get_wrong_root(), &proof));
+        }
 
-            assert!(verify_proof(get_root(&mt), &proof))
+        let binary_proof = get_proof(&get_test_tree(MORE_TEST_ELEMENTS.to_vec()), 1)
+            .expect("Should have received a valid proof");
+        assert!(!binary_proof.to_json().contains("arity"));
+
+        let invalid_arity = r#"{"element":"a","siblings":[],"directions":[],"arity":1}"#;
+        assert!(MerkleProof::from_json(invalid_arity).is_err());
+    }
+
+    #[test]
+    fn proving_lazy_trees() {
+        let elements = (0..1000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mt = create_merkle_tree(&elements)
+            .expect("Should have received a valid tree given a generated input");
+        let lazy_mt = create_lazy_merkle_tree(&elements)
+            .expect("Should have received a valid lazy tree given a generated input");
+
+        let before = HASH_COUNT.with(|count| count.get());
+        let proofs = [3, 500, 999]
+            .iter()
+            .map(|&index| {
+                get_lazy_proof(&lazy_mt, index).expect("Should have received a valid proof")
+            })
+            .collect::<Vec<_>>();
+        let hashes = HASH_COUNT.with(|count| count.get()) - before;
+
+        for (proof, index) in proofs.iter().zip([3, 500, 999]) {
+            assert_eq!(
+                proof,
+                &get_proof(&mt, index).expect("Should have received a valid proof")
+            );
+            assert!(verify_proof(&get_root(&mt), proof));
+        }
+
+        assert!(hash_op_count(elements.len()).is_some_and(|count| hashes < count));
+        assert_eq!(hashes, lazy_mt.computed_nodes());
+        assert_eq!(get_lazy_root(&lazy_mt), get_root(&mt));
+        assert!(get_lazy_proof(&lazy_mt, lazy_mt.leaves().len()).is_err());
+    }
+
+    #[cfg(feature = "normalization")]
+    #[test]
+    fn normalizing_leaves() {
+        let elements = vec![
+            "some".to_string(),
+            "cafe\u{301}".to_string(), // decomposed, 'e' followed by a combining acute accent
+            "elements".to_string(),
+        ];
+
+        for normalize in [false, true] {
+            let mt = MerkleTreeBuilder::new()
+                .with_normalization(normalize)
+                .build(&elements)
+                .expect("Should have received a valid tree given a fixed input");
+
+            let mut proof = get_proof(&mt, 1).expect("Should have received a valid proof");
+            assert!(verify_proof(&get_root(&mt), &proof));
+
+            proof.element = "caf\u{e9}".to_string(); // composed
+            assert_eq!(verify_proof(&get_root(&mt), &proof), normalize);
+        }
+    }
+
+    #[test]
+    fn transforming_leaves_before_hashing() {
+        let canonical_builder = || {
+            MerkleTreeBuilder::new()
+                .with_leaf_transform(LeafTransform::TrimWhitespace)
+                .with_leaf_transform(LeafTransform::Lowercase)
+        };
+        let as_given = [
+            "  Alice@Example.com ",
+            "bob@example.com",
+            "carol@example.com",
+        ]
+        .map(String::from)
+        .to_vec();
+        let canonical = ["alice@example.com", "bob@example.com", "carol@example.com"]
+            .map(String::from)
+            .to_vec();
+
+        let mt = canonical_builder()
+            .build(&as_given)
+            .expect("Should have received a valid tree given a fixed input");
+        let canonical_mt = canonical_builder()
+            .build(&canonical)
+            .expect("Should have received a valid tree given a fixed input");
+        assert_eq!(get_root(&mt), get_root(&canonical_mt));
+        assert_eq!(mt.leaves()[0], as_given[0]);
+
+        let proof = get_proof(&mt, 0).expect("Should have received a valid proof");
+        let canonical_proof =
+            get_proof(&canonical_mt, 0).expect("Should have received a valid proof");
+        assert_eq!(proof.siblings, canonical_proof.siblings);
+        assert_eq!(proof.directions, canonical_proof.directions);
+        assert!(verify_proof(&get_root(&mt), &proof));
+        assert!(verify_proof(&get_root(&mt), &canonical_proof));
+
+        // the transform is opt-in, so the untransformed tree has a different root
+        let plain_mt = create_merkle_tree(&as_given)
+            .expect("Should have received a valid tree given a fixed input");
+        assert_ne!(get_root(&plain_mt), get_root(&mt));
+
+        let strip_dots =
+            LeafTransform::Custom("strip dots", Arc::new(|leaf| leaf.replace('.', "")));
+        let config = TreeConfig::default().with_leaf_transform(strip_dots.to_owned());
+        assert_eq!(config.hash_leaf("a.b.c"), hash_leaf("abc"));
+        assert_eq!(
+            config,
+            TreeConfig::default().with_leaf_transform(strip_dots)
+        );
+        assert_ne!(config, TreeConfig::default());
+    }
+
+    #[test]
+    fn proving_key_value_pairs() {
+        let pairs = [
+            ("carol", "7"),
+            ("alice", "3"),
+            ("bob", "5"),
+            ("dave", "1"),
+            ("erin", "9"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+
+        let kv_mt = create_kv_merkle_tree(&pairs).expect("Should have received a valid kv tree");
+        let proof = get_kv_proof(&kv_mt, "carol").expect("Should have received a valid proof");
+
+        assert_eq!((proof.key(), proof.value()), ("carol", "7"));
+        assert!(verify_kv_proof(&get_kv_root(&kv_mt), &proof));
+        assert!(!verify_kv_proof(&get_wrong_root(), &proof));
+
+        let mut tampered_proof = proof.clone();
+        tampered_proof.value = "8".to_string();
+        assert!(!verify_kv_proof(&get_kv_root(&kv_mt), &tampered_proof));
+
+        // moving bytes between the key and the value changes the leaf
+        let mut shifted_proof = proof;
+        shifted_proof.key = "caro".to_string();
+        shifted_proof.value = "l7".to_string();
+        assert!(!verify_kv_proof(&get_kv_root(&kv_mt), &shifted_proof));
+
+        let mut reordered_pairs = pairs.to_vec();
+        reordered_pairs.reverse();
+        let reordered_mt =
+            create_kv_merkle_tree(&reordered_pairs).expect("Should have received a valid kv tree");
+        assert_eq!(get_kv_root(&reordered_mt), get_kv_root(&kv_mt));
+
+        for (key, _) in &pairs {
+            let proof = get_kv_proof(&kv_mt, key).expect("Should have received a valid proof");
+            assert!(verify_kv_proof(&get_kv_root(&kv_mt), &proof));
+        }
+
+        assert!(get_kv_proof(&kv_mt, "frank").is_err());
+        reordered_pairs.push(("bob".to_string(), "6".to_string()));
+        assert!(create_kv_merkle_tree(&reordered_pairs).is_err());
+    }
+
+    #[test]
+    fn resuming_trees_from_their_frontier() {
+        let elements = (0..20).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        for count in 1..12 {
+            let mt = create_merkle_tree(&elements[..count].to_vec())
+                .expect("Should have received a valid tree given a generated input");
+            let tree_frontier = frontier(&mt).expect("Should have received the frontier");
+            let mut resumed = resume_from_frontier(&tree_frontier, count)
+                .expect("Should have resumed from a frontier of the tree");
+
+            assert_eq!(resumed.root(), get_root(&mt), "element count {count}");
+
+            for (appended, element) in elements[count..].iter().enumerate() {
+                resumed.append(element);
+
+                let rebuilt_mt = create_merkle_tree(&elements[..count + appended + 1].to_vec())
+                    .expect("Should have received a valid tree given a generated input");
+                assert_eq!(resumed.root(), get_root(&rebuilt_mt));
+            }
+
+            assert_eq!(resumed.element_count(), elements.len());
+        }
+
+        let duplicated_config = TreeConfig::default().with_odd_handling(OddHandling::DuplicateLast);
+        let duplicated_mt =
+            create_merkle_tree_with_config(&elements[..5], duplicated_config.to_owned())
+                .expect("Should have received a valid tree given a generated input");
+        let mut resumed = resume_from_frontier_with_config(
+            &frontier(&duplicated_mt).expect("Should have received the frontier"),
+            5,
+            duplicated_config.to_owned(),
+        )
+        .expect("Should have resumed from a frontier of the tree");
+        resumed.append(&elements[5]);
+        resumed.append(&elements[6]);
+        let rebuilt_mt = create_merkle_tree_with_config(&elements[..7], duplicated_config)
+            .expect("Should have received a valid tree given a generated input");
+        assert_eq!(resumed.root(), get_root(&rebuilt_mt));
+
+        assert!(resume_from_frontier(&[], 3).is_err());
+    }
+
+    #[test]
+    fn appending_to_merkle_mountain_ranges() {
+        use crate::merkle_tree::mmr::MMR;
+
+        let mut mmr = MMR::new();
+        assert!(mmr.root().is_err());
+        assert!(mmr.proof(0).is_err());
+
+        let mut roots = HashSet::new();
+
+        for (count, element) in INCREASINGLY_MORE_TEST_ELEMENTS.iter().enumerate() {
+            mmr.append(element);
+            assert_eq!(mmr.element_count(), count + 1);
+
+            let root = mmr.root().expect("Should have a root after an append");
+            assert!(roots.insert(root.to_string()));
+
+            // early elements stay provable against the evolved root
+            for (index, earlier) in INCREASINGLY_MORE_TEST_ELEMENTS[..=count].iter().enumerate() {
+                let proof = mmr
+                    .proof(index)
+                    .expect("Should have received a proof for an appended element");
+                assert!(MMR::verify(&root, earlier, &proof));
+                assert!(!MMR::verify(&root, "not_an_element", &proof));
+                assert_eq!(
+                    MMR::verify(&get_wrong_root(), earlier, &proof),
+                    VERIFY_PROOF_FAILED
+                );
+            }
+
+            assert!(mmr.proof(count + 1).is_err());
         }
+
+        // a single mountain is a complete tree, so its root is the same as the tree's
+        let test_mt = get_test_tree(INCREASINGLY_MORE_TEST_ELEMENTS.to_vec());
+        assert_eq!(mmr.root().expect("Should have a root"), get_root(&test_mt));
+
+        // proofs are of the peaks at the time, so they go stale once their mountain merges
+        let mut stale_mmr = MMR::new();
+        TEST_ELEMENTS
+            .iter()
+            .for_each(|element| stale_mmr.append(element));
+        let stale_proof = stale_mmr.proof(0).expect("Should have received a proof");
+        stale_mmr.append("appended");
+        let root = stale_mmr.root().expect("Should have a root");
+        assert!(!MMR::verify(&root, TEST_ELEMENTS[0], &stale_proof));
+        let fresh_proof = stale_mmr.proof(0).expect("Should have received a proof");
+        assert!(MMR::verify(&root, TEST_ELEMENTS[0], &fresh_proof));
     }
 }
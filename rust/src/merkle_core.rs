@@ -0,0 +1,172 @@
+// the tree and proof logic of `merkle_tree` for the default scheme, without `std`
+//
+// digests are kept as raw 32 byte arrays and the hash function is injected, so the only thing
+// needed from the platform is an allocator; nodes are still hashed over the lowercase hex of
+// their children, so roots are the same as those `create_merkle_tree` produces for the same
+// hash function
+//
+// how rows pair up into parents, where a node's sibling is, and how a path climbs back to the
+// root are defined once here, generic over the node type, and `merkle_tree` builds and
+// verifies through them
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub type Digest = [u8; 32];
+
+// a hash function the core can be built with, e.g. SHA-256 from whichever crate the target has
+pub type HashFn = fn(&[u8]) -> Digest;
+
+pub fn hash_leaf(hash: HashFn, leaf: &[u8]) -> Digest {
+    hash(leaf)
+}
+
+// a missing right child, as given to the last node of an odd row, contributes nothing to the
+// hashed input, the same as the empty node of `merkle_tree`
+pub fn hash_node(hash: HashFn, left: &Digest, right: Option<&Digest>) -> Digest {
+    let mut input = Vec::with_capacity(128);
+    push_hex(&mut input, left);
+
+    if let Some(right) = right {
+        push_hex(&mut input, right);
+    }
+
+    hash(&input)
+}
+
+fn push_hex(input: &mut Vec<u8>, digest: &Digest) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    digest.iter().for_each(|byte| {
+        input.push(HEX[(byte >> 4) as usize]);
+        input.push(HEX[(byte & 0x0f) as usize]);
+    });
+}
+
+// the shape of every tree, shared with `merkle_tree`, whatever its nodes are and however they
+// are hashed
+
+// the parents of a row, pairing each node with the one after it; the last node of an odd row
+// is given `None` as its right sibling, for `parent` to pair it with whatever the scheme does
+pub fn parent_row<T>(row: Vec<T>, mut parent: impl FnMut(T, Option<T>) -> T) -> Vec<T> {
+    let mut parents = Vec::with_capacity(row.len().div_ceil(2));
+    let mut nodes = row.into_iter();
+
+    while let Some(left) = nodes.next() {
+        let right = nodes.next();
+        parents.push(parent(left, right));
+    }
+
+    parents
+}
+
+// the position of the sibling of the node at `position` in a row of `row_len` nodes, and
+// whether it is on the left; `None` for the last node of an odd row, which has none
+pub fn sibling_position(row_len: usize, position: usize) -> (Option<usize>, bool) {
+    if position % 2 == 1 {
+        (Some(position - 1), true)
+    } else if position + 1 < row_len {
+        (Some(position + 1), false)
+    } else {
+        (None, false)
+    }
+}
+
+// climb a path of `(sibling, is_left_child)` pairs from a leaf up, `parent` hashing the running
+// node with each sibling on the side the direction gives, or giving up on the path with `None`
+pub fn fold_path<T, S>(
+    leaf: T,
+    path: impl IntoIterator<Item = (S, bool)>,
+    mut parent: impl FnMut(T, S, bool) -> Option<T>,
+) -> Option<T> {
+    path.into_iter()
+        .try_fold(leaf, |current, (sibling, is_left_child)| {
+            parent(current, sibling, is_left_child)
+        })
+}
+
+pub struct CoreTree {
+    levels: Vec<Vec<Digest>>, // hashes of every row, from the leaves up to the root
+}
+
+pub struct CoreProof {
+    pub siblings: Vec<Option<Digest>>, // path of siblings from the element up to the root, `None` for an empty node
+    pub directions: Vec<bool>, // signal if the sibling at the same index is on the left or right
+}
+
+impl CoreTree {
+    // build a tree with the leaves padded to an even count with an empty element
+    pub fn build(hash: HashFn, elements: &[&[u8]]) -> Result<CoreTree, String> {
+        if elements.is_empty() {
+            return Err(String::from("A tree needs at least one element"));
+        }
+
+        let mut current_row = elements
+            .iter()
+            .map(|element| hash_leaf(hash, element))
+            .collect::<Vec<_>>();
+
+        if current_row.len() % 2 == 1 {
+            current_row.push(hash_leaf(hash, b""));
+        }
+
+        let mut levels = Vec::new();
+
+        while current_row.len() > 1 {
+            levels.push(current_row.to_vec());
+            current_row = parent_row(current_row, |left, right| {
+                hash_node(hash, &left, right.as_ref())
+            });
+        }
+
+        levels.push(current_row);
+
+        Ok(CoreTree { levels })
+    }
+
+    pub fn root(&self) -> Digest {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    pub fn proof(&self, index: usize) -> Result<CoreProof, String> {
+        if index >= self.levels[0].len() {
+            return Err(String::from(
+                "Index of the target element is out of bounds for this tree",
+            ));
+        }
+
+        let (siblings, directions) = self.levels[..self.levels.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(level, row)| {
+                let (sibling, is_left_child) = sibling_position(row.len(), index >> level);
+                (sibling.map(|sibling| row[sibling]), is_left_child)
+            })
+            .unzip();
+
+        Ok(CoreProof {
+            siblings,
+            directions,
+        })
+    }
+}
+
+// verify that `element` is part of the tree with the given root
+pub fn verify(hash: HashFn, root: &Digest, element: &[u8], proof: &CoreProof) -> bool {
+    if proof.siblings.len() != proof.directions.len() {
+        return false;
+    }
+
+    let computed = fold_path(
+        hash_leaf(hash, element),
+        proof.siblings.iter().zip(proof.directions.iter().copied()),
+        |current, sibling, is_left_child| match (sibling, is_left_child) {
+            (Some(sibling), true) => Some(hash_node(hash, sibling, Some(&current))),
+            (sibling, false) => Some(hash_node(hash, &current, sibling.as_ref())),
+            // an empty node is only ever paired on the right
+            (None, true) => None,
+        },
+    );
+
+    computed.as_ref() == Some(root)
+}